@@ -1,47 +1,73 @@
-use std::error::Error;
-use std::fmt::{Display, Formatter};
+use std::time::Duration;
 
-use reqwest::{RequestBuilder, StatusCode};
+use futures::StreamExt;
+
+use reqwest::{Certificate, NoProxy, Proxy, RequestBuilder, StatusCode};
 use reqwest::header::{ACCEPT, AUTHORIZATION, HeaderMap, USER_AGENT};
-use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
 
-use crate::git_dir::GitDir;
-use crate::github_license::{GithubLicense, MiniGithubLicense};
+use crate::api_error::{ApiError, ApiHost};
+use crate::github_license::{GithubLicense, GitlabLicense, MiniGithubLicense};
+use crate::output_printer::PrintMode;
+use crate::rate_limiter::RateLimiter;
 use crate::settings_file::ProgramSettings;
 
-/// Represents an API error.
-#[derive(Serialize, Deserialize)]
-pub struct ApiError {
-    message: String,
-    documentation_url: String,
-}
+static GITHUB_LICENSES_PATH: &str = "/licenses";
+static GITLAB_LICENSES_PATH: &str = "/api/v4/templates/licenses";
 
-impl ApiError {
-    /// Formats the error code and canonical reason as a string.
-    ///
-    /// # Arguments
-    ///
-    /// * `status` - The `StatusCode` for the error.
-    ///
-    /// # Returns
-    ///
-    /// A formatted string with the error code and canonical reason.
-    pub fn with_error_code(self, status: StatusCode) -> String {
-        format!("\t\tError Code: {}\n\t\tCanonical Reason: {:?}", status.as_str(), status.canonical_reason())
-    }
+/// How many license bodies are fetched at the same time.
+static MAX_CONCURRENT_REQUESTS: usize = 8;
+/// How often a failed request is retried.
+static MAX_RETRIES: u32 = 4;
+/// The longest time to wait for a rate limit reset, before giving up.
+static MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// Returns the URL of the licenses endpoint of the configured GitHub API.
+///
+/// # Arguments
+///
+/// * `program_settings` - The program settings, holding the `api_base_url`.
+fn github_licenses_url(program_settings: &ProgramSettings) -> String {
+    format!("{}{}", program_settings.api_base_url.trim_end_matches('/'), GITHUB_LICENSES_PATH)
 }
 
-impl Display for ApiError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "\t\tGithub API returned an error!\n\
-        \t\tMessage: {}\n\
-        \t\tURL for the Documentation: {}", self.message, self.documentation_url)
+/// Builds the HTTP client for all requests, honoring the CA bundle and proxy settings.
+///
+/// # Arguments
+///
+/// * `program_settings` - The program settings.
+///
+/// # Returns
+///
+/// The configured `reqwest::Client`.
+///
+/// # Errors
+///
+/// Returns an `ApiError::Configuration` if the CA bundle cannot be read or parsed, if a proxy URL is invalid,
+/// or if the client cannot be built.
+async fn build_client(program_settings: &ProgramSettings) -> Result<reqwest::Client, ApiError> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(ca_bundle_path) = &program_settings.ca_bundle_path {
+        let pem_bundle = tokio::fs::read(ca_bundle_path)
+            .await
+            .map_err(|err| ApiError::Configuration(format!("{} occurred while reading the CA bundle {}", err, ca_bundle_path)))?;
+        let certificates = Certificate::from_pem_bundle(&pem_bundle)
+            .map_err(|err| ApiError::Configuration(format!("The CA bundle {} is not valid: {}", ca_bundle_path, err)))?;
+        builder = builder.tls_certs_merge(certificates);
+    }
+    let no_proxy = program_settings.no_proxy.as_deref().and_then(NoProxy::from_string);
+    let invalid_proxy = |err: reqwest::Error| ApiError::Configuration(format!("Invalid proxy URL: {}", err));
+    if let Some(http_proxy) = &program_settings.http_proxy {
+        builder = builder.proxy(Proxy::http(http_proxy).map_err(invalid_proxy)?.no_proxy(no_proxy.clone()));
+    }
+    if let Some(https_proxy) = &program_settings.https_proxy {
+        builder = builder.proxy(Proxy::https(https_proxy).map_err(invalid_proxy)?.no_proxy(no_proxy));
     }
+    builder
+        .build()
+        .map_err(|err| ApiError::Configuration(format!("The HTTP client could not be built: {}", err)))
 }
 
-
-static GITHUB_API_URL: &str = "https://api.github.com/licenses";
-
 /// Sets the headers for the HTTP request builder.
 ///
 /// # Arguments
@@ -56,98 +82,307 @@ fn set_header(req: RequestBuilder, program_settings: &ProgramSettings) -> Reques
     let mut headers = HeaderMap::new();
 
     if let Some(auth) = &program_settings.github_api_token {
-        headers.insert(AUTHORIZATION, format!("Bearer: {}", auth).parse().unwrap());
+        headers.insert(AUTHORIZATION, format!("Bearer {}", auth).parse().unwrap());
     }
     headers.insert(USER_AGENT, "frequency403".parse().unwrap());
     headers.insert(ACCEPT, "application/vnd.github+json".parse().unwrap());
-    headers.insert("X-GitHub-Api-Version", "2022-11-28".parse().unwrap());
+    if let Some(api_version) = &program_settings.api_version {
+        if let Ok(value) = api_version.parse() {
+            headers.insert("X-GitHub-Api-Version", value);
+        }
+    }
     req.headers(headers)
 }
 
+/// Sends a request, retrying transient failures and respecting the rate limit of the API.
+///
+/// Network errors, server errors and rate limited responses (429, or 403 with an exhausted
+/// rate limit) are retried up to `MAX_RETRIES` times. The delay is taken from the `Retry-After`
+/// or `X-RateLimit-Reset` headers, or an exponential backoff with jitter. If the API asks to
+/// wait longer than `MAX_RATE_LIMIT_WAIT`, the request is not retried.
+///
+/// # Arguments
+///
+/// * `req` - The request to send, it must be cloneable (no streaming body).
+/// * `rate_limiter` - The `RateLimiter` shared by all requests against the same API.
+/// * `host` - The API the request goes to, for the hints of its errors.
+///
+/// # Returns
+///
+/// The body of the OK response.
+///
+/// # Errors
+///
+/// Returns the `ApiError` matching the last failure, if the request fails permanently.
+async fn send_with_retry(
+    req: RequestBuilder,
+    rate_limiter: &RateLimiter,
+    host: ApiHost,
+) -> Result<String, ApiError> {
+    let url = req
+        .try_clone()
+        .and_then(|builder| builder.build().ok())
+        .map(|request| request.url().to_string())
+        .unwrap_or_default();
+    let mut attempt: u32 = 0;
+    loop {
+        rate_limiter.wait().await;
+        let request = req
+            .try_clone()
+            .ok_or_else(|| ApiError::Configuration(format!("The request to {} can not be retried", url)))?;
+        match request.send().await {
+            Ok(response) => {
+                let status = response.status();
+                let headers = response.headers().clone();
+                let body = response.text().await.map_err(|err| ApiError::from_request(&url, err))?;
+                if status == StatusCode::OK {
+                    return Ok(body);
+                }
+                let exhausted_for = RateLimiter::exhausted_for(&headers);
+                let retry_after = RateLimiter::retry_after(&headers);
+                let rate_limited = status == StatusCode::TOO_MANY_REQUESTS
+                    || (status == StatusCode::FORBIDDEN && (exhausted_for.is_some() || retry_after.is_some()));
+                if (rate_limited || status.is_server_error()) && attempt < MAX_RETRIES {
+                    let wait = retry_after
+                        .or(exhausted_for)
+                        .unwrap_or_else(|| RateLimiter::backoff_with_jitter(attempt));
+                    if wait <= MAX_RATE_LIMIT_WAIT {
+                        rate_limiter.pause_for(wait);
+                        attempt += 1;
+                        continue;
+                    }
+                }
+                return Err(ApiError::from_response(host, &url, status, &headers, &body));
+            }
+            Err(err) if attempt < MAX_RETRIES && (err.is_timeout() || err.is_connect()) => {
+                tokio::time::sleep(RateLimiter::backoff_with_jitter(attempt)).await;
+                attempt += 1;
+            }
+            Err(err) => return Err(ApiError::from_request(&url, err)),
+        }
+    }
+}
+
+/// Parses the body of a response.
+///
+/// # Arguments
+///
+/// * `url` - The URL of the request, for the error message.
+/// * `body` - The body of the response.
+///
+/// # Errors
+///
+/// Returns an `ApiError::MalformedJson` if the body cannot be parsed.
+fn parse_body<T: DeserializeOwned>(url: &str, body: &str) -> Result<T, ApiError> {
+    serde_json::from_str::<T>(body).map_err(|source| ApiError::MalformedJson { url: url.to_string(), source })
+}
+
 /// Retrieves information about all licenses from the GitHub API.
 ///
+/// The license list is requested first, then the bodies of the licenses are fetched
+/// concurrently, with at most `MAX_CONCURRENT_REQUESTS` requests at the same time.
+/// A license that could not be fetched is recorded in the `ErrorCollector` and skipped.
+///
 /// # Arguments
 ///
 /// * `program_settings` - The program settings.
+/// * `print_mode` - A mutable reference to the `PrintMode` for recording the errors of single licenses.
 ///
 /// # Returns
 ///
-/// A vector of `GithubLicense` objects retrieved from the GitHub API.
+/// A vector of `GithubLicense` objects retrieved from the GitHub API, and whether all licenses
+/// of the list could be fetched.
 ///
 /// # Errors
 ///
-/// Returns a `Box<dyn Error>` if an error occurs during the retrieval process. Possible errors include:
-/// * Network errors when making HTTP requests
-/// * Deserialization errors when parsing the API response
-/// * API error responses with error code and message
-///
+/// Returns an `ApiError` if the license list cannot be retrieved, or the last error if none of its licenses could be fetched.
 pub async fn get_all_licenses(
     program_settings: &ProgramSettings,
-) -> Result<Vec<GithubLicense>, Box<dyn Error>> {
-    let client = reqwest::Client::new();
-    let mut req = client.get(GITHUB_API_URL);
+    print_mode: &mut PrintMode,
+) -> Result<(Vec<GithubLicense>, bool), ApiError> {
+    let client = build_client(program_settings).await?;
+    let rate_limiter = RateLimiter::new();
+    let url = github_licenses_url(program_settings);
+    let mut req = client.get(&url);
     req = set_header(req, program_settings);
-    let mut full_obj: Vec<GithubLicense> = vec![];
 
+    let request_body = send_with_retry(req, &rate_limiter, ApiHost::Github).await?;
+    let minis = parse_body::<Vec<MiniGithubLicense>>(&url, &request_body)?;
 
-    let request_sender = req.send().await?;
-    let status = request_sender.status();
-    let request_body = request_sender.text().await?;
-    if status == StatusCode::OK {
-        if let Ok(msg) = serde_json::from_str::<ApiError>(&request_body) {
-            return Err(Box::from(msg.with_error_code(status)));
-        }
-        for mini in
-        serde_json::from_str::<Vec<MiniGithubLicense>>(&request_body)?
-        {
-            let mut rq = client.get(mini.url);
-            rq = set_header(rq, program_settings);
-            let rqs = rq.send().await?;
-            let status = rqs.status();
-            if rqs.status() == StatusCode::OK {
-                let full_license =
-                    serde_json::from_str::<GithubLicense>(rqs.text().await?.as_str())?;
-                full_obj.push(full_license);
-            } else {
-                if let Ok(msg) = serde_json::from_str::<ApiError>(rqs.text().await?.as_str()) {
-                    return Err(Box::from(msg.with_error_code(status)));
-                }
-                return Err(Box::from("Did not recognize the Response Error Type."));
+    let (client, rate_limiter) = (&client, &rate_limiter);
+    let results = futures::stream::iter(minis.iter())
+        .map(|mini| async move {
+            (mini, get_license(client, &mini.url, program_settings, rate_limiter).await)
+        })
+        .buffer_unordered(MAX_CONCURRENT_REQUESTS)
+        .collect::<Vec<_>>()
+        .await;
+
+    let mut full_obj: Vec<GithubLicense> = vec![];
+    let mut last_error: Option<ApiError> = None;
+    for (mini, result) in results {
+        match result {
+            Ok(license) => full_obj.push(license),
+            Err(err) => {
+                print_mode.error_msg(format!("Could not fetch the license \"{}\" [{}]: {}", mini.name, err.kind(), err));
+                last_error = Some(err);
             }
         }
-    } else {
-        return Err(Box::try_from(request_body).unwrap());
     }
-    Ok(full_obj)
+    if let Some(err) = last_error {
+        if full_obj.is_empty() {
+            return Err(err);
+        }
+    }
+    // Keep the order of the API, the concurrent requests finish in any order.
+    full_obj.sort_by_key(|license| minis.iter().position(|mini| mini.key == license.key));
+    let complete = full_obj.len() == minis.len();
+    Ok((full_obj, complete))
 }
 
-/// Retrieves a README template from a given URL and replaces a specified phrase with the project title.
+/// Retrieves a single license from the GitHub API.
+///
+/// # Arguments
+///
+/// * `client` - The `reqwest::Client` used for the request.
+/// * `url` - The API URL of the license, like `https://api.github.com/licenses/mit`.
+/// * `program_settings` - The program settings.
+/// * `rate_limiter` - The `RateLimiter` shared by all requests against the GitHub API.
+///
+/// # Returns
+///
+/// The `GithubLicense` retrieved from the GitHub API.
+///
+/// # Errors
+///
+/// Returns an `ApiError` on network errors, deserialization errors or API error responses.
+async fn get_license(
+    client: &reqwest::Client,
+    url: &str,
+    program_settings: &ProgramSettings,
+    rate_limiter: &RateLimiter,
+) -> Result<GithubLicense, ApiError> {
+    let mut rq = client.get(url);
+    rq = set_header(rq, program_settings);
+    let body = send_with_retry(rq, rate_limiter, ApiHost::Github).await?;
+    parse_body::<GithubLicense>(url, &body)
+}
+
+/// Retrieves a single license by its key from the GitHub API.
+///
+/// # Arguments
+///
+/// * `program_settings` - The program settings.
+/// * `key` - The GitHub key of the license, like `mit` or `apache-2.0`.
+///
+/// # Returns
+///
+/// The `GithubLicense` retrieved from the GitHub API.
+///
+/// # Errors
+///
+/// Returns an `ApiError` on network errors, deserialization errors or API error responses.
+pub async fn get_license_by_key(
+    program_settings: &ProgramSettings,
+    key: &str,
+) -> Result<GithubLicense, ApiError> {
+    let client = build_client(program_settings).await?;
+    get_license(&client, &format!("{}/{}", github_licenses_url(program_settings), key), program_settings, &RateLimiter::new()).await
+}
+
+/// Retrieves all license templates from a GitLab instance.
+///
+/// # Arguments
+///
+/// * `program_settings` - The program settings, used for the CA bundle and proxy settings.
+/// * `base_url` - The base URL of the GitLab instance, like `https://gitlab.com`.
+/// * `token` - An optional personal access token, sent as `PRIVATE-TOKEN` header.
+///
+/// # Returns
+///
+/// A vector of `GithubLicense` objects, converted from the GitLab templates.
+///
+/// # Errors
+///
+/// Returns an `ApiError` on network errors, deserialization errors or a non-OK response.
+pub async fn get_gitlab_licenses(
+    program_settings: &ProgramSettings,
+    base_url: &str,
+    token: &Option<String>,
+) -> Result<Vec<GithubLicense>, ApiError> {
+    let url = format!("{}{}", base_url.trim_end_matches('/'), GITLAB_LICENSES_PATH);
+    let body = get_gitlab(program_settings, &url, token).await?;
+    Ok(parse_body::<Vec<GitlabLicense>>(&url, &body)?
+        .into_iter()
+        .map(GithubLicense::from)
+        .collect())
+}
+
+/// Retrieves a single license template by its key from a GitLab instance.
+///
+/// # Arguments
+///
+/// * `program_settings` - The program settings, used for the CA bundle and proxy settings.
+/// * `base_url` - The base URL of the GitLab instance, like `https://gitlab.com`.
+/// * `token` - An optional personal access token, sent as `PRIVATE-TOKEN` header.
+/// * `key` - The GitLab key of the license, like `mit` or `apache-2.0`.
+///
+/// # Returns
+///
+/// The `GithubLicense` converted from the GitLab template.
+///
+/// # Errors
+///
+/// Returns an `ApiError` on network errors, deserialization errors or a non-OK response.
+pub async fn get_gitlab_license(
+    program_settings: &ProgramSettings,
+    base_url: &str,
+    token: &Option<String>,
+    key: &str,
+) -> Result<GithubLicense, ApiError> {
+    let url = format!("{}{}/{}", base_url.trim_end_matches('/'), GITLAB_LICENSES_PATH, key);
+    let body = get_gitlab(program_settings, &url, token).await?;
+    Ok(GithubLicense::from(parse_body::<GitlabLicense>(&url, &body)?))
+}
+
+/// Sends a GET request to the GitLab API and returns the response body.
+///
+/// # Arguments
+///
+/// * `program_settings` - The program settings, used for the CA bundle and proxy settings.
+/// * `url` - The full URL of the request.
+/// * `token` - An optional personal access token, sent as `PRIVATE-TOKEN` header.
+///
+/// # Errors
+///
+/// Returns an `ApiError` on network errors or a non-OK response.
+async fn get_gitlab(program_settings: &ProgramSettings, url: &str, token: &Option<String>) -> Result<String, ApiError> {
+    let client = build_client(program_settings).await?;
+    let mut req = client.get(url).header(USER_AGENT, "frequency403");
+    if let Some(token) = token {
+        req = req.header("PRIVATE-TOKEN", token);
+    }
+    send_with_retry(req, &RateLimiter::new(), ApiHost::Gitlab).await
+}
+
+/// Downloads the README template from the `readme_template_link`.
 ///
 /// # Arguments
 ///
 /// * `program_settings` - A reference to the `ProgramSettings` struct containing the program settings.
-/// * `directory` - A reference to the `GitDir` struct representing the project directory.
 ///
 /// # Returns
 ///
-/// Returns an `Option<String>` that contains the README template with the specified phrase replaced by the project title, or `None` if an error occurred
-pub async fn get_readme_template(
+/// Returns an `Option<String>` that contains the README template, or `None` if an error occurred
+pub async fn download_readme_template(
     program_settings: &ProgramSettings,
-    directory: &GitDir,
 ) -> Option<String> {
-    let client = reqwest::Client::new();
+    let client = build_client(program_settings).await.ok()?;
     let mut request_builder = client.get(program_settings.readme_template_link.as_str());
     request_builder = set_header(request_builder, program_settings);
 
     if let Ok(response) = request_builder.send().await {
-        if let Ok(body) = response.text().await {
-            Some(body.replace(
-                &program_settings.replace_in_readme_phrase,
-                &directory.project_title,
-            ))
-        } else {
-            None
-        }
+        response.text().await.ok()
     } else {
         None
     }
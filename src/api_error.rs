@@ -0,0 +1,261 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Local, TimeZone};
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+
+use crate::rate_limiter::RateLimiter;
+
+/// The error body the GitHub API sends along with a non-OK response.
+#[derive(Serialize, Deserialize)]
+struct GithubErrorBody {
+    message: String,
+    #[serde(default)]
+    documentation_url: String,
+}
+
+/// Checks whether an error, or one of its sources, is a failure of the TLS connection, like an unknown certificate authority.
+///
+/// # Arguments
+///
+/// * `err` - The error.
+fn is_tls_error(err: &(dyn Error + 'static)) -> bool {
+    let mut current: Option<&(dyn Error + 'static)> = Some(err);
+    while let Some(err) = current {
+        if err.is::<rustls::Error>() {
+            return true;
+        }
+        // The source of an io::Error is the source of the error it wraps, so the wrapped error is checked on its own.
+        if let Some(inner) = err.downcast_ref::<std::io::Error>().and_then(|io_error| io_error.get_ref()) {
+            if is_tls_error(inner) {
+                return true;
+            }
+        }
+        current = err.source();
+    }
+    false
+}
+
+/// The API a request was sent to, it decides how the user passes a token.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ApiHost {
+    /// The GitHub API, or the `api_base_url` of a GitHub Enterprise server.
+    Github,
+    /// A GitLab instance of a `gitlab_api` license source.
+    Gitlab,
+}
+
+impl ApiHost {
+    /// Returns how to fix the credentials, after the API rejected them.
+    fn auth_hint(&self) -> &'static str {
+        match self {
+            ApiHost::Github => "Check the token set with --github-token.",
+            ApiHost::Gitlab => "Check the \"token\" of the \"gitlab_api\" license source in the settings file.",
+        }
+    }
+
+    /// Returns how to raise the rate limit.
+    fn rate_limit_hint(&self) -> &'static str {
+        match self {
+            ApiHost::Github => "Set a token with --github-token to raise the limit.",
+            ApiHost::Gitlab => "Set the \"token\" of the \"gitlab_api\" license source in the settings file to raise the limit.",
+        }
+    }
+}
+
+/// Represents an error while talking to a license API.
+///
+/// Every variant has a stable `kind` and `exit_code`, so scripts can tell the failures apart.
+#[derive(Debug)]
+pub enum ApiError {
+    /// The settings for the HTTP client (CA bundle, proxy) are invalid.
+    Configuration(String),
+    /// The server could not be reached.
+    Network { url: String, source: reqwest::Error },
+    /// The TLS handshake failed, mostly because of an unknown certificate authority.
+    Tls { url: String, source: reqwest::Error },
+    /// The API rejected the credentials, or the request needs some.
+    Auth { host: ApiHost, url: String, status: StatusCode, message: String },
+    /// The rate limit of the API is exhausted.
+    RateLimited { host: ApiHost, url: String, reset_at: Option<DateTime<Local>> },
+    /// The requested resource does not exist.
+    NotFound { url: String },
+    /// The response could not be parsed.
+    MalformedJson { url: String, source: serde_json::Error },
+    /// Any other error response of the API.
+    Api { url: String, status: StatusCode, message: String, documentation_url: String },
+}
+
+impl ApiError {
+    /// Creates the error for a failed request.
+    ///
+    /// TLS failures are told apart from other network errors by a `rustls` error in the error chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the request.
+    /// * `source` - The error of `reqwest`.
+    pub fn from_request(url: &str, source: reqwest::Error) -> Self {
+        if is_tls_error(&source) {
+            ApiError::Tls { url: url.to_string(), source }
+        } else {
+            ApiError::Network { url: url.to_string(), source }
+        }
+    }
+
+    /// Creates the error for a non-OK response.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The API the request was sent to.
+    /// * `url` - The URL of the request.
+    /// * `status` - The status code of the response.
+    /// * `headers` - The headers of the response, used for the rate limit reset time.
+    /// * `body` - The body of the response.
+    pub fn from_response(host: ApiHost, url: &str, status: StatusCode, headers: &HeaderMap, body: &str) -> Self {
+        let url = url.to_string();
+        let error_body = serde_json::from_str::<GithubErrorBody>(body).ok();
+        let exhausted_for = RateLimiter::exhausted_for(headers);
+        let retry_after = RateLimiter::retry_after(headers);
+        let rate_limited = status == StatusCode::TOO_MANY_REQUESTS
+            || (status == StatusCode::FORBIDDEN
+            && (exhausted_for.is_some()
+            || retry_after.is_some()
+            || error_body.as_ref().is_some_and(|b| b.message.to_lowercase().contains("rate limit"))));
+
+        if rate_limited {
+            let reset_at = retry_after
+                .or(exhausted_for)
+                .and_then(|wait| chrono::Duration::from_std(wait).ok())
+                .map(|wait| Local::now() + wait)
+                .or_else(|| RateLimiter::reset_timestamp(headers).and_then(|reset| Local.timestamp_opt(reset as i64, 0).single()));
+            return ApiError::RateLimited { host, url, reset_at };
+        }
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ApiError::Auth {
+                host,
+                url,
+                status,
+                message: error_body.map(|b| b.message).unwrap_or_else(|| body.to_string()),
+            },
+            StatusCode::NOT_FOUND => ApiError::NotFound { url },
+            _ => match error_body {
+                Some(b) => ApiError::Api { url, status, message: b.message, documentation_url: b.documentation_url },
+                None => ApiError::Api { url, status, message: body.to_string(), documentation_url: String::new() },
+            },
+        }
+    }
+
+    /// Returns the machine readable kind of the error.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ApiError::Configuration(_) => "configuration",
+            ApiError::Network { .. } => "network",
+            ApiError::Tls { .. } => "tls",
+            ApiError::Auth { .. } => "auth",
+            ApiError::RateLimited { .. } => "rate_limited",
+            ApiError::NotFound { .. } => "not_found",
+            ApiError::MalformedJson { .. } => "malformed_json",
+            ApiError::Api { .. } => "api",
+        }
+    }
+
+    /// Returns the exit code of the program, if it stops because of this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ApiError::Configuration(_) => 10,
+            ApiError::Network { .. } => 11,
+            ApiError::Tls { .. } => 12,
+            ApiError::Auth { .. } => 13,
+            ApiError::RateLimited { .. } => 14,
+            ApiError::NotFound { .. } => 15,
+            ApiError::MalformedJson { .. } => 16,
+            ApiError::Api { .. } => 17,
+        }
+    }
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiError::Configuration(message) => write!(f, "Invalid HTTP settings: {}", message),
+            ApiError::Network { url, source } => write!(f, "Could not reach {}: {}", url, source),
+            ApiError::Tls { url, source } => write!(
+                f,
+                "TLS error while connecting to {}: {}\nIf your network uses its own certificate authority, set \"ca_bundle_path\" in the settings file.",
+                url, source
+            ),
+            ApiError::Auth { host, url, status, message } => write!(
+                f,
+                "The API at {} rejected the request ({}): {}\n{}",
+                url,
+                status,
+                message,
+                host.auth_hint()
+            ),
+            ApiError::RateLimited { host, url, reset_at } => {
+                match reset_at {
+                    Some(reset_at) => write!(f, "The rate limit of {} is exhausted, it resets at {}.", url, reset_at.format("%H:%M"))?,
+                    None => write!(f, "The rate limit of {} is exhausted.", url)?,
+                }
+                write!(f, " {}", host.rate_limit_hint())
+            }
+            ApiError::NotFound { url } => write!(f, "{} does not exist (404 Not Found)", url),
+            ApiError::MalformedJson { url, source } => write!(f, "The response of {} is not valid: {}", url, source),
+            ApiError::Api { url, status, message, documentation_url } => {
+                write!(f, "The API at {} returned an error!\n\t\tError Code: {}\n\t\tCanonical Reason: {:?}\n\t\tMessage: {}", url, status.as_str(), status.canonical_reason(), message)?;
+                if !documentation_url.is_empty() {
+                    write!(f, "\n\t\tURL for the Documentation: {}", documentation_url)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for ApiError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ApiError::Network { source, .. } | ApiError::Tls { source, .. } => Some(source),
+            ApiError::MalformedJson { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use super::*;
+
+    #[test]
+    fn finds_rustls_errors_wrapped_in_io_errors() {
+        let certificate_error = rustls::Error::InvalidCertificate(rustls::CertificateError::UnknownIssuer);
+        assert!(is_tls_error(&certificate_error));
+        assert!(is_tls_error(&std::io::Error::new(ErrorKind::InvalidData, certificate_error)));
+        let nested = std::io::Error::other(std::io::Error::new(ErrorKind::InvalidData, rustls::Error::DecryptError));
+        assert!(is_tls_error(&nested));
+    }
+
+    #[test]
+    fn hints_at_the_token_of_the_failed_api() {
+        let headers = HeaderMap::new();
+        let github = ApiError::from_response(ApiHost::Github, "https://api.github.com/licenses", StatusCode::UNAUTHORIZED, &headers, "");
+        assert!(github.to_string().ends_with("--github-token."));
+        let gitlab = ApiError::from_response(ApiHost::Gitlab, "https://gitlab.example.com/api/v4/templates/licenses", StatusCode::UNAUTHORIZED, &headers, "");
+        assert!(gitlab.to_string().contains("\"gitlab_api\" license source"));
+        assert!(!gitlab.to_string().contains("--github-token"));
+        let rate_limited = ApiError::from_response(ApiHost::Gitlab, "https://gitlab.example.com", StatusCode::TOO_MANY_REQUESTS, &headers, "");
+        assert!(matches!(rate_limited, ApiError::RateLimited { host: ApiHost::Gitlab, .. }));
+        assert!(!rate_limited.to_string().contains("--github-token"));
+    }
+
+    #[test]
+    fn does_not_match_the_text_of_other_errors() {
+        assert!(!is_tls_error(&std::io::Error::new(ErrorKind::NotFound, "certificate.pem not found")));
+        assert!(!is_tls_error(&std::io::Error::new(ErrorKind::ConnectionRefused, "TLS port closed")));
+    }
+}
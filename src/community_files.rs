@@ -0,0 +1,196 @@
+use std::path::Path;
+
+use strum_macros::EnumIter;
+
+use crate::github_license::GithubLicense;
+use crate::license_link_style::LicenseLinkStyle;
+
+// Community files are the documents next to the license, that tell others how to take part
+// in a project. Missing ones are created from the built-in templates, or from the templates in
+// the `community_templates_directory`, which use the same variables as the README templates.
+
+/// The heading of the README section, that links the community files.
+static COMMUNITY_HEADING: &str = "## Community";
+
+/// Directories, that GitHub also searches for community files.
+static COMMUNITY_DIRECTORIES: [&str; 3] = ["", ".github", "docs"];
+
+/// The SPDX id of the only license, that asks for a NOTICE file.
+static NOTICE_LICENSE: &str = "Apache-2.0";
+
+/// A community file of a repository.
+#[derive(PartialEq, Copy, Clone, EnumIter, Debug)]
+pub enum CommunityFile {
+    Contributing,
+    CodeOfConduct,
+    Security,
+    Notice, //Only for Apache-2.0 licensed repositories.
+}
+
+impl CommunityFile {
+    /// Returns the file name of the community file.
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            CommunityFile::Contributing => "CONTRIBUTING.md",
+            CommunityFile::CodeOfConduct => "CODE_OF_CONDUCT.md",
+            CommunityFile::Security => "SECURITY.md",
+            CommunityFile::Notice => "NOTICE",
+        }
+    }
+
+    /// Returns the title, used for the link in the README.
+    pub fn title(&self) -> &'static str {
+        match self {
+            CommunityFile::Contributing => "Contributing",
+            CommunityFile::CodeOfConduct => "Code of Conduct",
+            CommunityFile::Security => "Security Policy",
+            CommunityFile::Notice => "Notice",
+        }
+    }
+
+    /// Returns the built-in template of the community file.
+    pub fn built_in_template(&self) -> &'static str {
+        match self {
+            CommunityFile::Contributing => include_str!("../resources/community/CONTRIBUTING.md"),
+            CommunityFile::CodeOfConduct => include_str!("../resources/community/CODE_OF_CONDUCT.md"),
+            CommunityFile::Security => include_str!("../resources/community/SECURITY.md"),
+            CommunityFile::Notice => include_str!("../resources/community/NOTICE"),
+        }
+    }
+
+    /// Checks whether a repository with the given license needs this community file.
+    ///
+    /// # Arguments
+    ///
+    /// * `license` - The license of the repository, if any.
+    pub fn is_needed_for(&self, license: Option<&GithubLicense>) -> bool {
+        match self {
+            CommunityFile::Notice => license.is_some_and(|l| l.spdx_id.eq_ignore_ascii_case(NOTICE_LICENSE)),
+            _ => true,
+        }
+    }
+
+    /// Checks whether the community file exists in a repository.
+    ///
+    /// Like GitHub, the root, `.github` and `docs` directories are searched, ignoring the case of the file name.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the repository.
+    pub fn exists_in(&self, path: &str) -> bool {
+        COMMUNITY_DIRECTORIES.iter().any(|directory| {
+            std::fs::read_dir(Path::new(path).join(directory))
+                .map(|entries| {
+                    entries.filter_map(|entry| entry.ok()).any(|entry| {
+                        entry.file_name().to_string_lossy().eq_ignore_ascii_case(self.file_name())
+                    })
+                })
+                .unwrap_or(false)
+        })
+    }
+
+    /// Returns the target of the link to the community file, in the `license_link_style` of the settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `style` - The `license_link_style` of the settings.
+    /// * `web_url` - The web page of the repository, like `https://github.com/owner/name`, if it has a remote.
+    ///
+    /// # Returns
+    ///
+    /// A relative link like `./CONTRIBUTING.md`, or for the external and SPDX styles the file on the web page
+    /// of the repository. Community files have no page on spdx.org, and without a remote the relative link is used.
+    pub fn link_target(&self, style: LicenseLinkStyle, web_url: Option<&str>) -> String {
+        match (style, web_url) {
+            (LicenseLinkStyle::External | LicenseLinkStyle::Spdx, Some(web_url)) => {
+                format!("{}/blob/HEAD/{}", web_url.trim_end_matches('/'), self.file_name())
+            }
+            _ => format!("./{}", self.file_name()),
+        }
+    }
+
+    /// Returns the Markdown link to the community file, for the README.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The target of the link, see `link_target`.
+    pub fn get_markdown_link(&self, target: &str) -> String {
+        format!("\n[{}]({})", self.title(), target)
+    }
+
+    /// Loads the template of the community file.
+    ///
+    /// # Arguments
+    ///
+    /// * `templates_directory` - The `community_templates_directory` of the settings, if any.
+    ///
+    /// # Returns
+    ///
+    /// The file of the same name in the templates directory, or the built-in template.
+    pub async fn load_template(&self, templates_directory: &Option<String>) -> String {
+        if let Some(directory) = templates_directory {
+            if let Ok(template) = tokio::fs::read_to_string(Path::new(directory).join(self.file_name())).await {
+                return template;
+            }
+        }
+        self.built_in_template().to_string()
+    }
+}
+
+/// Adds the links of community files to the "## Community" section of a README.
+///
+/// The section is created before the license section, or at the end if there is none.
+/// Links that are already in the README (in any style) are not added again.
+///
+/// # Arguments
+///
+/// * `readme_path` - The path of the README.
+/// * `files` - The community files to link.
+/// * `style` - The `license_link_style` of the settings.
+/// * `web_url` - The web page of the repository, for the external and SPDX styles.
+///
+/// # Returns
+///
+/// Whether the README changed.
+///
+/// # Errors
+///
+/// Returns an error if the README cannot be read or written.
+pub async fn link_in_readme(
+    readme_path: &Path,
+    files: &[CommunityFile],
+    style: LicenseLinkStyle,
+    web_url: Option<&str>,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let content = tokio::fs::read_to_string(readme_path).await?;
+    let links = files
+        .iter()
+        .filter(|file| !content.contains(&format!("{})", file.file_name())))
+        .map(|file| file.get_markdown_link(&file.link_target(style, web_url)))
+        .collect::<String>();
+    if links.is_empty() {
+        return Ok(false);
+    }
+
+    let lines = content.lines().collect::<Vec<&str>>();
+    let new_content = if let Some(index) = lines
+        .iter()
+        .position(|line| line.trim().eq_ignore_ascii_case(COMMUNITY_HEADING))
+    {
+        [lines[..=index].join("\n"), links, "\n".to_string(), lines[index + 1..].join("\n"), "\n".to_string()].concat()
+    } else if let Some(index) = lines.iter().position(|line| {
+        line.starts_with("##") && line.trim_start_matches('#').trim().eq_ignore_ascii_case("license")
+    }) {
+        [
+            lines[..index].join("\n"),
+            format!("\n\n{}\n{}\n\n", COMMUNITY_HEADING, links),
+            lines[index..].join("\n"),
+            "\n".to_string(),
+        ]
+            .concat()
+    } else {
+        format!("{}\n\n{}\n{}\n", content.trim_end(), COMMUNITY_HEADING, links)
+    };
+    tokio::fs::write(readme_path, new_content).await?;
+    Ok(true)
+}
@@ -0,0 +1,148 @@
+use std::error::Error;
+
+use crate::github_license::GithubLicense;
+use crate::output_printer::PrintMode;
+
+// Custom licenses are plain text files inside the configured "custom_licenses_directory".
+// Each file starts with a front-matter block, enclosed by two "---" lines, that describes the
+// license in the same terms the GitHub API does. Everything after the closing "---" is the
+// license body, which may use the "[fullname]" and "[year]" placeholders like the GitHub templates.
+//
+// ---
+// key: acme-proprietary
+// name: ACME Proprietary License
+// spdx_id: LicenseRef-ACME-Proprietary
+// description: Internal license for ACME code
+// permissions: [private-use]
+// conditions: [include-copyright]
+// limitations: [liability, warranty]
+// ---
+// Copyright (c) [year] [fullname]
+// ...
+
+static FRONT_MATTER_DELIMITER: &str = "---";
+
+/// Splits a comma separated front-matter list like `[a, b, c]` or `a, b, c` into its entries.
+///
+/// # Arguments
+///
+/// * `value` - The raw value of the front-matter key.
+///
+/// # Returns
+///
+/// A `Vec<String>` with the trimmed, non-empty entries.
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(|entry| entry.trim().trim_matches('"').to_string())
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+/// Parses the content of a custom license file into a `GithubLicense`.
+///
+/// # Arguments
+///
+/// * `content` - The complete file content, front-matter and body.
+///
+/// # Returns
+///
+/// The parsed `GithubLicense`.
+///
+/// # Errors
+///
+/// Returns an error if the front-matter block is missing or not closed, if one of the
+/// required keys `key`, `name` or `spdx_id` is missing, or if the body is empty.
+pub fn parse_custom_license(content: &str) -> Result<GithubLicense, Box<dyn Error>> {
+    let mut lines = content.lines();
+    if lines.next().map(str::trim) != Some(FRONT_MATTER_DELIMITER) {
+        return Err(Box::from("File does not start with a front-matter block"));
+    }
+
+    let mut license = GithubLicense::default();
+
+    let mut closed = false;
+    for line in lines.by_ref() {
+        if line.trim() == FRONT_MATTER_DELIMITER {
+            closed = true;
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim().trim_matches('"').to_string();
+            match key.trim() {
+                "key" => license.key = value,
+                "name" => license.name = value,
+                "spdx_id" => license.spdx_id = value,
+                "url" => license.url = value,
+                "html_url" => license.html_url = value,
+                "description" => license.description = value,
+                "implementation" => license.implementation = value,
+                "permissions" => license.permissions = parse_list(&value),
+                "conditions" => license.conditions = parse_list(&value),
+                "limitations" => license.limitations = parse_list(&value),
+                "featured" => license.featured = value == "true",
+                _ => {}
+            }
+        }
+    }
+
+    if !closed {
+        return Err(Box::from("Front-matter block is not closed with \"---\""));
+    }
+    if license.key.is_empty() || license.name.is_empty() || license.spdx_id.is_empty() {
+        return Err(Box::from("Front-matter needs at least \"key\", \"name\" and \"spdx_id\""));
+    }
+
+    license.body = lines.collect::<Vec<&str>>().join("\n").trim().to_string();
+    if license.body.is_empty() {
+        return Err(Box::from("License body is empty"));
+    }
+    license.body.push('\n');
+    Ok(license)
+}
+
+/// Loads all custom licenses from the given directory.
+///
+/// Every regular file in the directory is parsed with `parse_custom_license`. Files that
+/// cannot be read or parsed are reported through the `PrintMode` and skipped.
+///
+/// # Arguments
+///
+/// * `directory` - The path of the directory holding the custom license files.
+/// * `print_mode` - A mutable reference to the `PrintMode` for reporting errors.
+///
+/// # Returns
+///
+/// A `Vec<GithubLicense>` with all successfully parsed licenses, sorted by name.
+pub async fn load_custom_licenses(directory: &str, print_mode: &mut PrintMode) -> Vec<GithubLicense> {
+    let mut licenses: Vec<GithubLicense> = vec![];
+    let mut entries = match tokio::fs::read_dir(directory).await {
+        Ok(entries) => entries,
+        Err(err) => {
+            print_mode.error_msg(format!("{} occurred while reading the custom licenses directory {}", err, directory));
+            return licenses;
+        }
+    };
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) => match parse_custom_license(&content) {
+                Ok(license) => {
+                    print_mode.verbose_msg(format!("Loaded custom license \"{}\" from {}", license.name, path.display()), None);
+                    licenses.push(license)
+                }
+                Err(err) => print_mode.error_msg(format!("Skipped custom license {}: {}", path.display(), err)),
+            },
+            Err(err) => print_mode.error_msg(format!("{} occurred while reading {}", err, path.display())),
+        }
+    }
+    licenses.sort_by(|a, b| a.name.cmp(&b.name));
+    licenses
+}
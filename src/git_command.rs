@@ -0,0 +1,225 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+
+use tokio::process::Command;
+
+// Runs the git command line tool inside of a repository, for the changes license-me made.
+
+/// Runs git in a working tree and returns its raw output, regardless of the exit code.
+///
+/// # Arguments
+///
+/// * `work_dir` - The path of the working tree.
+/// * `args` - The arguments for git.
+///
+/// # Errors
+///
+/// Returns an error if git cannot be started.
+pub async fn git_output(work_dir: &str, args: &[&str]) -> Result<Output, Box<dyn Error>> {
+    Command::new("git")
+        .arg("-C")
+        .arg(work_dir)
+        .args(args)
+        .output()
+        .await
+        .map_err(|error| Box::from(format!("Could not run git: {}", error)))
+}
+
+/// Runs git in a working tree.
+///
+/// # Arguments
+///
+/// * `work_dir` - The path of the working tree.
+/// * `args` - The arguments for git.
+///
+/// # Returns
+///
+/// The standard output of git.
+///
+/// # Errors
+///
+/// Returns an error with the standard error of git, if git fails.
+pub async fn run_git(work_dir: &str, args: &[&str]) -> Result<String, Box<dyn Error>> {
+    let output = git_output(work_dir, args).await?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(Box::from(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+/// Returns the paths of a working tree that are staged for the next commit.
+///
+/// # Arguments
+///
+/// * `work_dir` - The path of the working tree.
+pub async fn staged_files(work_dir: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    Ok(run_git(work_dir, &["diff", "--cached", "--name-only", "-z"])
+        .await?
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Returns the path of a file relative to the working tree, as git expects it.
+///
+/// # Arguments
+///
+/// * `work_dir` - The path of the working tree.
+/// * `file` - The path of the file.
+pub fn relative_path(work_dir: &str, file: &Path) -> String {
+    file.strip_prefix(work_dir)
+        .unwrap_or(file)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// The options of a commit.
+pub struct CommitOptions<'a> {
+    /// The commit message.
+    pub(crate) message: &'a str,
+    /// Adds a `Signed-off-by` trailer.
+    pub(crate) signoff: bool,
+    /// Overrides the author, like `Jane Doe <jane@example.com>`.
+    pub(crate) author: Option<&'a str>,
+}
+
+/// Stages exactly the given files and commits them.
+///
+/// # Arguments
+///
+/// * `work_dir` - The path of the working tree.
+/// * `files` - The files license-me touched, deleted files included.
+/// * `options` - The message, signoff and author of the commit.
+///
+/// # Returns
+///
+/// `true` if a commit was created, `false` if the files did not change.
+///
+/// # Errors
+///
+/// Returns an error if other changes are already staged, or if git fails.
+pub async fn commit_files(work_dir: &str, files: &[PathBuf], options: &CommitOptions<'_>) -> Result<bool, Box<dyn Error>> {
+    let paths = files.iter().map(|file| relative_path(work_dir, file)).collect::<Vec<String>>();
+    let unrelated = staged_files(work_dir)
+        .await?
+        .into_iter()
+        .filter(|staged| !paths.contains(staged))
+        .collect::<Vec<String>>();
+    if !unrelated.is_empty() {
+        return Err(Box::from(format!(
+            "Refusing to commit, other changes are already staged: {}",
+            unrelated.join(", ")
+        )));
+    }
+
+    // Deleted files can only be staged, if git knows them.
+    let mut ls_args = vec!["ls-files", "-z", "--"];
+    ls_args.extend(paths.iter().map(String::as_str));
+    let tracked = run_git(work_dir, &ls_args).await?;
+    let tracked = tracked.split('\0').collect::<Vec<&str>>();
+    let paths = files
+        .iter()
+        .zip(paths.iter())
+        .filter(|(file, path)| file.exists() || tracked.contains(&path.as_str()))
+        .map(|(_, path)| path.as_str())
+        .collect::<Vec<&str>>();
+    if paths.is_empty() {
+        return Ok(false);
+    }
+
+    let mut add_args = vec!["add", "-A", "--"];
+    add_args.extend(paths);
+    run_git(work_dir, &add_args).await?;
+    if git_output(work_dir, &["diff", "--cached", "--quiet"]).await?.status.success() {
+        return Ok(false);
+    }
+
+    let mut commit_args = vec!["commit", "-m", options.message];
+    if options.signoff {
+        commit_args.push("--signoff");
+    }
+    let author_arg;
+    if let Some(author) = options.author {
+        author_arg = format!("--author={}", author);
+        commit_args.push(&author_arg);
+    }
+    run_git(work_dir, &commit_args).await?;
+    Ok(true)
+}
+
+/// Returns what HEAD points at, so it can be checked out again: the branch name, or the commit on a detached HEAD.
+///
+/// # Arguments
+///
+/// * `work_dir` - The path of the working tree.
+///
+/// # Errors
+///
+/// Returns an error if the repository has no commit yet.
+pub async fn current_ref(work_dir: &str) -> Result<String, Box<dyn Error>> {
+    let output = git_output(work_dir, &["symbolic-ref", "--short", "-q", "HEAD"]).await?;
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
+    }
+    Ok(run_git(work_dir, &["rev-parse", "HEAD"]).await?.trim().to_string())
+}
+
+/// Turns a license expression into a part of a branch name, like `mit-or-apache-2.0`.
+///
+/// # Arguments
+///
+/// * `expression` - The license expression.
+pub fn branch_slug(expression: &str) -> String {
+    expression
+        .to_lowercase()
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '-'))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-")
+}
+
+/// Creates a branch at HEAD and checks it out, keeping the changes of the working tree.
+///
+/// # Arguments
+///
+/// * `work_dir` - The path of the working tree.
+/// * `branch` - The name of the new branch.
+///
+/// # Errors
+///
+/// Returns an error if the name is invalid or the branch already exists.
+pub async fn create_branch(work_dir: &str, branch: &str) -> Result<(), Box<dyn Error>> {
+    run_git(work_dir, &["check-ref-format", "--branch", branch]).await?;
+    run_git(work_dir, &["checkout", "-q", "-b", branch]).await?;
+    Ok(())
+}
+
+/// Writes the commits of a range as patch files, like `git format-patch` does.
+///
+/// # Arguments
+///
+/// * `work_dir` - The path of the working tree.
+/// * `range` - The commits to export, like `main..license-me/add-mit`.
+/// * `output_dir` - The directory of the patch files, created if it is missing.
+///
+/// # Returns
+///
+/// The paths of the written patch files.
+pub async fn format_patches(work_dir: &str, range: &str, output_dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    tokio::fs::create_dir_all(output_dir).await?;
+    let output_dir = std::path::absolute(output_dir)?;
+    let output_dir = output_dir.to_string_lossy();
+    Ok(run_git(work_dir, &["format-patch", "-o", &output_dir, range])
+        .await?
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
@@ -1,17 +1,30 @@
 use std::fmt::{Display, Formatter};
 use std::path::{MAIN_SEPARATOR, Path, PathBuf};
 
-use async_recursion::async_recursion;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
 use tokio::fs::File;
 use tokio::io::AsyncReadExt;
 
 use crate::ask_a_question;
 use crate::alike::is_alike;
-use crate::api_communicator::get_readme_template;
+use crate::community_files::{link_in_readme, CommunityFile};
+use crate::git_command::{branch_slug, commit_files, create_branch, current_ref, format_patches, relative_path, run_git, CommitOptions};
+use crate::git_metadata::RepositoryMetadata;
 use crate::github_license::GithubLicense;
+use crate::license_badge::{badge_markdown, update_badge};
+use crate::license_exception::{find_exception_file, split_exception, LicenseException};
+use crate::license_expression::{license_file_name, spdx_id_of_file_suffix, LicenseExpression};
+use crate::license_link_style::LicenseLinkStyle;
 use crate::operating_mode::OperatingMode;
 use crate::output_printer::PrintMode;
+use crate::ownership::Ownership;
+use crate::project_metadata::{manifest_license, write_manifest_license};
+use crate::readme_template::{create_readme, TemplateVariables};
+use crate::repo_status::changed_guarded_files;
+use crate::repository_kind::RepositoryKind;
 use crate::settings_file::ProgramSettings;
+use crate::spdx::{canonical_license_id, deprecated_replacement, is_license_ref, same_license};
 
 static README_VARIANTS: [&str; 6] = [
     "README",
@@ -24,16 +37,33 @@ static README_VARIANTS: [&str; 6] = [
 static LICENSE_VARIANTS: [&str; 3] = ["LICENSE", "license", "License"];
 
 static DEFAULT_LICENSE_FILE: &str = "LICENSE";
+
+/// Extensions of license files, that are not part of the license id in `LICENSE-*` names.
+static LICENSE_FILE_EXTENSIONS: [&str; 4] = [".txt", ".md", ".markdown", ".rst"];
 static DEFAULT_README_FILE: &str = "README.md";
 
 /// Represents a directory containing a Git repository.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct GitDir {
     pub(crate) path: String,
     pub(crate) readme_path: Option<PathBuf>,
     pub(crate) license_path: Option<PathBuf>,
     pub(crate) project_title: String,
     pub(crate) license: Option<GithubLicense>,
+    /// All licenses of the repository, like `MIT OR Apache-2.0` for dual-licensed repositories.
+    pub(crate) license_expression: Option<LicenseExpression>,
+    /// The files license-me created, changed or deleted in the repository, for the commit.
+    #[serde(skip)]
+    pub(crate) touched_files: Vec<PathBuf>,
+    /// The files license-me writes, that had uncommitted changes before license-me edited the repository.
+    #[serde(skip)]
+    pub(crate) changed_before_edit: Vec<String>,
+    /// The branch, remotes and history of the repository.
+    pub(crate) metadata: RepositoryMetadata,
+    /// Whether the repository is a worktree, submodule, nested or bare repository.
+    pub(crate) kind: RepositoryKind,
+    /// The paths of the other worktrees of the repository, that are not listed on their own.
+    pub(crate) worktrees: Vec<String>,
 }
 
 impl GitDir {
@@ -42,7 +72,7 @@ impl GitDir {
     /// # Arguments
     ///
     /// * `path` - A string representing the path to the project.
-    /// * `licenses` - An optional vector of `GithubLicense` representing the available licenses.
+    /// * `licenses` - The available licenses, to recognize the license of the repository.
     ///
     /// # Returns
     ///
@@ -58,70 +88,140 @@ impl GitDir {
     ///     let path = String::from("path/to/project");
     ///     let licenses = vec![GithubLicense { body: String::from("MIT License") }];
     ///
-    ///     let result = init(path, Some(licenses)).await;
+    ///     let result = init(path, Some(&licenses)).await;
     ///     assert_eq!(result.path, "path/to/project");
     ///     assert_eq!(result.readme_path, None);
     ///     assert_eq!(result.license_path, None);
     ///     assert_eq!(result.project_title, "project");
     ///     assert_eq!(result.license, None);
+    ///     assert_eq!(result.license_expression, None);
     /// }
     /// ```
-    pub async fn init(path: String, licenses: Option<Vec<GithubLicense>>) -> Self {
-        let clean_path = path.replace(format!("{}.git", MAIN_SEPARATOR).as_str(), "");
-        let project_title = clean_path.split(MAIN_SEPARATOR).last().unwrap().to_string();
+    pub async fn init(path: String, licenses: Option<&[GithubLicense]>) -> Self {
+        // The walker passes the ".git" entry of a working tree, or the directory of a bare repository.
+        let clean_path = path
+            .strip_suffix(format!("{}.git", MAIN_SEPARATOR).as_str())
+            .unwrap_or(&path)
+            .to_string();
+        let kind = RepositoryKind::detect(&clean_path).await;
+        let project_title = clean_path.split(MAIN_SEPARATOR).next_back().unwrap().to_string();
+        let project_title = match kind {
+            RepositoryKind::Bare => project_title.strip_suffix(".git").unwrap_or(&project_title).to_string(),
+            _ => project_title,
+        };
 
         let mut readme_path: Option<PathBuf> = None;
         let mut license_path: Option<PathBuf> = None;
 
-        //TODO find a better and elegant way for this block of code.
-        README_VARIANTS.into_iter().for_each(|readme_name| {
-            if readme_path.is_none() {
-                let temp_pth = format!("{}{}{}", &clean_path, MAIN_SEPARATOR, readme_name);
-                readme_path = if Path::new(temp_pth.as_str()).exists() {
-                    Some(temp_pth.into())
-                } else {
-                    None
-                };
+        for readme_name in README_VARIANTS {
+            let temp_pth = format!("{}{}{}", &clean_path, MAIN_SEPARATOR, readme_name);
+            if tokio::fs::try_exists(&temp_pth).await.unwrap_or(false) {
+                readme_path = Some(temp_pth.into());
+                break;
             }
-        });
+        }
 
-        LICENSE_VARIANTS.into_iter().for_each(|license_name| {
-            if license_path.is_none() {
-                let temp_pth = format!("{}{}{}", &clean_path, MAIN_SEPARATOR, license_name);
-                license_path = if Path::new(temp_pth.as_str()).exists() {
-                    Some(temp_pth.into())
-                } else {
-                    None
-                };
+        for license_name in LICENSE_VARIANTS {
+            let temp_pth = format!("{}{}{}", &clean_path, MAIN_SEPARATOR, license_name);
+            if tokio::fs::try_exists(&temp_pth).await.unwrap_or(false) {
+                license_path = Some(temp_pth.into());
+                break;
             }
-        });
-        // END block
+        }
 
-        let license_holder = if let Some(license_vec) = licenses {
+        let (license_holder, exception) = if let Some(license_vec) = licenses {
             if let Some(found_license) = &license_path {
                 if let Ok(license_content) = tokio::fs::read_to_string(found_license).await {
-                    license_vec
-                        .into_iter()
-                        .filter(|available_licenses| {
-                            is_alike(available_licenses.clone().body, license_content.clone(), 60)
-                        })
-                        .last()
+                    recognize_license(license_vec, &license_content)
                 } else {
-                    None
+                    (None, None)
                 }
             } else {
-                None
+                (None, None)
             }
         } else {
-            None
+            (None, None)
+        };
+        // The exception is either appended to the license file, or shipped beside it.
+        let exception = match exception {
+            Some(exception) => Some(exception),
+            None => find_exception_file(&clean_path).await.map(|(_, exception)| exception),
         };
 
+        // Dual-licensed repositories have a file per license, like LICENSE-MIT and LICENSE-APACHE.
+        let dual_licenses = find_dual_licenses(&clean_path, licenses.unwrap_or_default()).await;
+        // A plain LICENSE beside them is one of the licenses, as long as it was recognized.
+        let plain_license = match (&license_path, &license_holder) {
+            (Some(_), Some(license)) => Some(LicenseExpression::License {
+                id: license.spdx_id.clone(),
+                exception: exception.map(|exception| exception.id.to_string()),
+            }),
+            _ => None,
+        };
+        let (license_path, license_holder) = match (license_path, dual_licenses.first()) {
+            (None, Some((path, license, _))) => (Some(path.clone()), license.clone()),
+            (license_path, _) => (license_path, license_holder),
+        };
+
+        // The manifest declares the expression of the repository, else it is made up of the license files.
+        let license_expression = match manifest_license(&clean_path).await.map(|e| LicenseExpression::parse(&e)) {
+            Some(Ok(expression)) => Some(expression.normalize().unwrap_or(expression)),
+            _ if !dual_licenses.is_empty() => {
+                let mut alternatives: Vec<LicenseExpression> = plain_license.into_iter().collect();
+                for (path, license, exception) in &dual_licenses {
+                    let alternative = LicenseExpression::License {
+                        id: match license {
+                            Some(license) => license.spdx_id.clone(),
+                            None => spdx_id_of_file_suffix(&license_file_suffix(path)),
+                        },
+                        exception: exception.map(|exception| exception.id.to_string()),
+                    };
+                    if !alternatives.contains(&alternative) {
+                        alternatives.push(alternative);
+                    }
+                }
+                alternatives.into_iter().reduce(LicenseExpression::or)
+            }
+            _ => license_holder.as_ref().map(|license| LicenseExpression::License {
+                id: license.spdx_id.clone(),
+                exception: exception.map(|exception| exception.id.to_string()),
+            }),
+        };
+
+        let metadata = RepositoryMetadata::read(&clean_path).await;
+
         Self {
             path: clean_path,
             readme_path,
             license_path,
             project_title,
             license: license_holder,
+            license_expression,
+            touched_files: vec![],
+            changed_before_edit: vec![],
+            metadata,
+            kind,
+            worktrees: vec![],
+        }
+    }
+
+    /// Classifies the repository as owned, foreign or local only, by its remotes.
+    ///
+    /// # Arguments
+    ///
+    /// * `owners` - The owned users and organizations.
+    pub fn ownership(&self, owners: &[String]) -> Ownership {
+        Ownership::of(&self.metadata, owners)
+    }
+
+    /// Remembers a file license-me created, changed or deleted.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    fn touch(&mut self, path: PathBuf) {
+        if !self.touched_files.contains(&path) {
+            self.touched_files.push(path);
         }
     }
 
@@ -199,7 +299,7 @@ impl GitDir {
     ///         } else {
     ///             print_mode.error_msg("Failure during README file content creation");
     ///         }
-    ///         self.license_path = Some(dummy_path);
+    ///         self.readme_path = Some(dummy_path);
     ///     }
     /// }
     /// # }
@@ -211,41 +311,37 @@ impl GitDir {
     ) {
         if self.readme_path.is_none() {
             let dummy_path = self.get_default_readme_path();
-            if let Some(readme) = get_readme_template(program_settings, &self.clone()).await {
-                if let Err(error) = tokio::fs::write(&dummy_path, readme).await {
-                    print_mode.error_msg("Failure during README file creation");
-                    print_mode.error_msg(error);
+            if let Some(readme) = create_readme(program_settings, &self.clone()).await {
+                match tokio::fs::write(&dummy_path, readme).await {
+                    Ok(_) => self.touch(PathBuf::from(&dummy_path)),
+                    Err(error) => {
+                        print_mode.error_msg("Failure during README file creation");
+                        print_mode.error_msg(error);
+                    }
                 }
             } else {
                 print_mode.error_msg("Failure during README file content creation");
             }
-            self.license_path = Some(PathBuf::from(dummy_path));
+            self.readme_path = Some(PathBuf::from(&dummy_path));
         }
     }
 
-    /// Replaces the license section in the readme file with a new license.
+    /// Replaces the license section in the readme file.
     ///
     /// # Arguments
     ///
-    /// * `license` - A reference to the `GithubLicense` object representing the new license.
+    /// * `section` - The new content of the license section, see `license_section`.
     /// * `pm` - A mutable reference to the `PrintMode` object for printing messages.
-    /// * `multi_license` - A boolean value indicating whether the project has multiple licenses.
     ///
     /// # Examples
     ///
     /// ```
     /// let mut printer = PrintMode::new();
-    /// let license = GithubLicense::new("MIT", "https://opensource.org/licenses/MIT");
     /// let mut project = Project::new();
     ///
-    /// project.replace_in_readme(&license, &mut printer, false).await;
+    /// project.replace_in_readme("\n[MIT](./LICENSE)", &mut printer).await;
     /// ```
-    async fn replace_in_readme(
-        &self,
-        license: &GithubLicense,
-        pm: &mut PrintMode,
-        multi_license: bool,
-    ) {
+    async fn replace_in_readme(&mut self, section: &str, pm: &mut PrintMode) {
         // declaring placeholders outside of "if let" scope
         let mut new_file_content = String::new();
         let mut new_license_section = String::new();
@@ -276,38 +372,12 @@ impl GitDir {
                             || c.contains(" License\n")
                             || c.contains(" LICENSE\n")
                     }) {
-                        //TODO Implement a "is like" function to make this block even more accurate. - Works best when there is a "License" inside of the GitDir struct
-
-                        // Then replace it
+                        // Then replace it, the section of multiple licenses lists all of them
                         if let Some(content) = slices_of_old_file.last() {
-                            if multi_license {
-                                if content == &slices_of_old_file[index_of_license]
-                                    || is_alike(content, &slices_of_old_file[index_of_license], 70)
-                                {
-                                    new_license_section = [
-                                        slices_of_old_file[index_of_license],
-                                        "\n",
-                                        &license.get_markdown_license_link(),
-                                    ]
-                                        .concat()
-                                } else {
-                                    new_license_section = [
-                                        slices_of_old_file[index_of_license]
-                                            .replace("##", "")
-                                            .as_str(),
-                                        "\n",
-                                        &license.get_markdown_license_link(),
-                                        "\n\n##",
-                                    ]
-                                        .concat()
-                                }
-                            } else if content == &slices_of_old_file[index_of_license] {
-                                new_license_section =
-                                    [" License\n", &license.get_markdown_license_link()].concat()
+                            if content == &slices_of_old_file[index_of_license] {
+                                new_license_section = [" License\n", section].concat()
                             } else {
-                                new_license_section =
-                                    [" License\n", &license.get_markdown_license_link(), "\n\n##"]
-                                        .concat()
+                                new_license_section = [" License\n", section, "\n\n##"].concat()
                             }
                         }
                         slices_of_old_file[index_of_license] = &new_license_section;
@@ -319,14 +389,20 @@ impl GitDir {
                     }
 
                     // Then overwrite the License file or print message on error
+                    if new_file_content == old_file_content {
+                        return;
+                    }
                     match tokio::fs::write(self.readme_path.clone().unwrap(), new_file_content).await {
-                        Ok(_) => pm.verbose_msg(
-                            format!(
-                                "Success in overwriting {}",
-                                self.readme_path.clone().unwrap().display()
-                            ),
-                            None,
-                        ),
+                        Ok(_) => {
+                            pm.verbose_msg(
+                                format!(
+                                    "Success in overwriting {}",
+                                    self.readme_path.clone().unwrap().display()
+                                ),
+                                None,
+                            );
+                            self.touch(path.clone());
+                        }
                         Err(msg) => pm.error_msg(format!(
                             "{} occurred while writing {}",
                             msg,
@@ -344,6 +420,43 @@ impl GitDir {
         }
     }
 
+    /// Returns the content of the README license section.
+    ///
+    /// A single license is a link, multiple licenses are listed with the wording of the expression,
+    /// like "Licensed under either of ... at your option.".
+    ///
+    /// # Arguments
+    ///
+    /// * `program_settings` - The program settings, for the `license_link_style`.
+    /// * `licenses` - All known licenses, for the names of the licenses in the expression.
+    pub fn license_section(&self, program_settings: &ProgramSettings, licenses: &[GithubLicense]) -> String {
+        match (&self.license_expression, &self.license) {
+            (Some(expression), _) if expression.license_ids().len() > 1 || expression.has_exception() => {
+                let wording = expression.readme_wording(|id| {
+                    let file = license_file_name(id);
+                    let license = licenses
+                        .iter()
+                        .find(|license| same_license(&license.spdx_id, id))
+                        .cloned()
+                        .unwrap_or_else(|| GithubLicense {
+                            name: id.to_string(),
+                            spdx_id: id.to_string(),
+                            ..GithubLicense::default()
+                        });
+                    let target = program_settings.license_link_style.target(&license, &file);
+                    if program_settings.license_link_style == LicenseLinkStyle::Relative {
+                        format!("{} ([{}]({}))", license.name, file, target)
+                    } else {
+                        format!("[{}]({})", license.name, target)
+                    }
+                });
+                format!("\n{}", wording)
+            }
+            (_, Some(license)) => license.get_markdown_license_link(&self.license_link(program_settings, license)),
+            _ => String::new(),
+        }
+    }
+
     /// Writes the license file.
     ///
     /// # Arguments
@@ -351,40 +464,389 @@ impl GitDir {
     /// * `program_settings` - The program settings.
     /// * `print_mode` - The print mode.
     /// * `user_choice` - The chosen GitHub license.
+    /// * `licenses` - All known licenses, for the README section of multiple licenses.
     /// * `multi_license` - Indicates whether multiple licenses are used.
-    #[async_recursion]
     async fn write_license(
         &mut self,
         program_settings: &ProgramSettings,
         print_mode: &mut PrintMode,
         user_choice: &GithubLicense,
+        licenses: &[GithubLicense],
         multi_license: bool,
     ) {
-        if self.license_path.is_none() || self.license.is_none() {
-            if let Err(error) = tokio::fs::write(
-                self.get_default_license_path(),
-                user_choice.clone().set_username_and_year().body,
-            )
-                .await
-            {
-                print_mode.error_msg(error);
-            }
-            self.license_path = Some(self.get_default_license_path().into());
+        // Appended licenses keep the file name chosen by the caller, like "LICENSE-MIT".
+        let license_path = match &self.license_path {
+            Some(path) if multi_license => path.clone(),
+            _ => PathBuf::from(self.get_default_license_path()),
+        };
+        let exception = program_settings
+            .preselected_exception
+            .as_deref()
+            .and_then(LicenseException::find);
+        let mut body = user_choice.clone().set_username_and_year().body;
+        if let Some(exception) = exception {
+            body.push_str(&exception.appendix());
+        }
+        match tokio::fs::write(&license_path, body).await {
+            Ok(_) => self.touch(license_path.clone()),
+            Err(error) => print_mode.error_msg(error),
+        }
+        self.license_path = Some(license_path);
+        let chosen_expression = LicenseExpression::License {
+            id: user_choice.spdx_id.clone(),
+            exception: exception.map(|exception| exception.id.to_string()),
+        };
+        self.license_expression = match (multi_license, self.license_expression.take(), &self.license) {
+            (true, Some(expression), _) => Some(expression.or(chosen_expression)),
+            (true, None, Some(license)) => Some(LicenseExpression::single(&license.spdx_id).or(chosen_expression)),
+            _ => Some(chosen_expression),
+        };
+        if !multi_license || self.license.is_none() {
             self.license = Some(user_choice.to_owned());
-            if self.readme_path.is_some() {
-                if multi_license {
-                    self.replace_in_readme(user_choice, print_mode, false).await;
+        }
+        if self.readme_path.is_some() {
+            if multi_license {
+                self.replace_in_readme(&self.license_section(program_settings, licenses), print_mode).await;
+            }
+        } else if ask_a_question("Found no README file - do you want to create one?") {
+            self.set_dummy_readme(program_settings, print_mode).await;
+            self.replace_in_readme(&self.license_section(program_settings, licenses), print_mode).await;
+        }
+        if program_settings.license_badge {
+            self.set_license_badge(program_settings, print_mode, Some(user_choice), multi_license).await;
+        }
+        if program_settings.update_manifests {
+            if let Some(expression) = self.license_expression.clone() {
+                match write_manifest_license(&self.path, &expression.to_string()).await {
+                    Ok((manifests, not_updated)) => {
+                        for manifest in manifests {
+                            print_mode.normal_msg(format!("Set the license of {} to \"{}\"", manifest.display(), expression));
+                            self.touch(manifest);
+                        }
+                        for reason in not_updated {
+                            print_mode.normal_msg(format!("{} {}", ansi_term::Color::Yellow.bold().paint("Warning:"), reason));
+                        }
+                    }
+                    Err(error) => print_mode.error_msg(format!("{} occurred while updating the manifests", error)),
+                }
+            }
+        }
+    }
+
+    /// Returns the target of the license links in the README, in the `license_link_style` of the settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `program_settings` - The program settings.
+    /// * `license` - The linked license.
+    pub fn license_link(&self, program_settings: &ProgramSettings, license: &GithubLicense) -> String {
+        let license_file = self
+            .license_path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| DEFAULT_LICENSE_FILE.to_string());
+        program_settings.license_link_style.target(license, &license_file)
+    }
+
+    /// Renames a plain `LICENSE` file to the name of its license, like `LICENSE-MIT`,
+    /// before another license is appended, so both licenses follow the same naming.
+    ///
+    /// # Arguments
+    ///
+    /// * `print_mode` - The print mode.
+    async fn rename_license_for_dual_layout(&mut self, print_mode: &mut PrintMode) {
+        let (Some(license_path), Some(license)) = (&self.license_path, &self.license) else {
+            return;
+        };
+        let is_plain = license_path
+            .file_name()
+            .is_some_and(|name| LICENSE_VARIANTS.contains(&name.to_string_lossy().as_ref()));
+        if !is_plain {
+            return;
+        }
+        let renamed = license_path.with_file_name(license_file_name(&license.spdx_id));
+        match tokio::fs::rename(license_path, &renamed).await {
+            Ok(_) => {
+                print_mode.verbose_msg(format!("Renamed {} to {}", license_path.display(), renamed.display()), None);
+                self.touch(license_path.clone());
+                self.touch(renamed.clone());
+                self.license_path = Some(renamed);
+            }
+            Err(error) => print_mode.error_msg(format!("{} occurred while renaming {}", error, license_path.display())),
+        }
+    }
+
+    /// Removes the files of the current license expression, before it is replaced: the license file,
+    /// the `LICENSE-*` files of the other licenses and an exception shipped in its own file.
+    ///
+    /// # Arguments
+    ///
+    /// * `print_mode` - The print mode.
+    /// * `licenses` - All known licenses, to recognize the license files.
+    ///
+    /// # Returns
+    ///
+    /// Whether all files were removed.
+    async fn remove_license_files(&mut self, print_mode: &mut PrintMode, licenses: &[GithubLicense]) -> bool {
+        let mut files: Vec<PathBuf> = self.license_path.clone().into_iter().collect();
+        files.extend(find_dual_licenses(&self.path, licenses).await.into_iter().map(|(path, _, _)| path));
+        if self.license_expression.as_ref().is_some_and(LicenseExpression::has_exception) {
+            files.extend(find_exception_file(&self.path).await.map(|(path, _)| path));
+        }
+        for (index, file) in files.iter().enumerate() {
+            if files[..index].contains(file) {
+                continue;
+            }
+            let file = file.clone();
+            match tokio::fs::remove_file(&file).await {
+                Ok(_) => {
+                    print_mode.verbose_msg(format!("Removed {}", file.display()), None);
+                    // The deletion is committed with the new license, also if the new one has another name.
+                    self.touch(file);
+                }
+                Err(error) => {
+                    print_mode.error_msg(format!("{} occurred while deleting {}", error, file.display()));
+                    return false;
+                }
+            }
+        }
+        self.license_path = None;
+        true
+    }
+
+    /// Inserts, updates or removes the license badge in the README, if there is one.
+    ///
+    /// # Arguments
+    ///
+    /// * `program_settings` - The program settings, for the `license_badge_url`.
+    /// * `print_mode` - The print mode.
+    /// * `license` - The license of the badge, or `None` to remove the badge.
+    /// * `multi_license` - Adds the badge next to the existing ones, instead of replacing them.
+    async fn set_license_badge(
+        &mut self,
+        program_settings: &ProgramSettings,
+        print_mode: &mut PrintMode,
+        license: Option<&GithubLicense>,
+        multi_license: bool,
+    ) {
+        if let Some(readme_path) = self.readme_path.clone() {
+            let badge = license.map(|license| {
+                badge_markdown(
+                    &program_settings.license_badge_url,
+                    license,
+                    &self.license_link(program_settings, license),
+                )
+            });
+            match update_badge(&readme_path, badge.as_deref(), multi_license).await {
+                Ok(true) => self.touch(readme_path),
+                Ok(false) => {}
+                Err(error) => print_mode.error_msg(format!(
+                    "{} occurred while updating the license badge in {}",
+                    error,
+                    readme_path.display()
+                )),
+            }
+        }
+    }
+
+    /// Returns the community files the repository needs, but does not have yet.
+    pub fn missing_community_files(&self) -> Vec<CommunityFile> {
+        CommunityFile::iter()
+            .filter(|file| file.is_needed_for(self.license.as_ref()) && !file.exists_in(&self.path))
+            .collect()
+    }
+
+    /// Creates the missing community files and links them in the README.
+    ///
+    /// # Arguments
+    ///
+    /// * `program_settings` - The program settings, for the `community_templates_directory`.
+    /// * `print_mode` - The print mode.
+    async fn add_community_files(&mut self, program_settings: &ProgramSettings, print_mode: &mut PrintMode) {
+        let missing = self.missing_community_files();
+        if missing.is_empty() {
+            print_mode.verbose_msg(format!("{} has all community files", self.project_title), None);
+            return;
+        }
+        let variables = TemplateVariables::resolve(self, program_settings).await;
+        let mut created: Vec<CommunityFile> = vec![];
+        for file in missing {
+            let template = file.load_template(&program_settings.community_templates_directory).await;
+            let path = Path::new(&self.path).join(file.file_name());
+            match tokio::fs::write(&path, variables.render(&template)).await {
+                Ok(_) => {
+                    print_mode.verbose_msg(format!("Created {}", path.display()), None);
+                    created.push(file);
+                    self.touch(path);
                 }
-            } else if ask_a_question("Found no README file - do you want to create one?") {
+                Err(error) => print_mode.error_msg(format!("{} occurred while writing {}", error, path.display())),
+            }
+        }
+        if created.is_empty() {
+            return;
+        }
+        if self.readme_path.is_none() {
+            if ask_a_question("Found no README file - do you want to create one, to link the community files in it?") {
                 self.set_dummy_readme(program_settings, print_mode).await;
-                self.replace_in_readme(user_choice, print_mode, multi_license).await;
+            } else {
+                print_mode.normal_msg(format!(
+                    "{}: Found no README, {} could not be linked",
+                    self.project_title,
+                    created.iter().map(|file| file.file_name()).collect::<Vec<&str>>().join(", ")
+                ));
+                return;
             }
-        } else {
-            self.write_license(program_settings, print_mode, user_choice, multi_license)
-                .await
+        }
+        if let Some(readme_path) = self.readme_path.clone() {
+            let web_url = self.metadata.main_remote().and_then(|remote| remote.web_url());
+            match link_in_readme(&readme_path, &created, program_settings.license_link_style, web_url.as_deref()).await {
+                Ok(true) => self.touch(readme_path),
+                Ok(false) => {}
+                Err(error) => print_mode.error_msg(format!(
+                    "{} occurred while linking the community files in {}",
+                    error,
+                    readme_path.display()
+                )),
+            }
+        }
+    }
+
+    /// Commits the files license-me touched, if `--commit` (or `--branch`) is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `program_settings` - The program settings, for the message template, signoff and author.
+    /// * `print_mode` - The print mode.
+    /// * `op_mode` - The operating mode, that decides the `{action}` of the message.
+    /// * `previous_expression` - The license expression before the change, used when the license was removed.
+    pub async fn commit_changes(
+        &mut self,
+        program_settings: &ProgramSettings,
+        print_mode: &mut PrintMode,
+        op_mode: &OperatingMode,
+        previous_expression: Option<LicenseExpression>,
+    ) {
+        if !(program_settings.commit_changes || program_settings.commit_on_branch) || self.touched_files.is_empty() {
+            return;
+        }
+        // The commit would take the edits of the user along with the ones of license-me.
+        let changed_before_edit = self
+            .touched_files
+            .iter()
+            .map(|file| relative_path(&self.path, file))
+            .filter(|file| self.changed_before_edit.contains(file))
+            .collect::<Vec<String>>();
+        if !changed_before_edit.is_empty() {
+            print_mode.error_msg(format!(
+                "{}: Refusing to commit, {} had uncommitted changes before license-me edited them",
+                self.project_title,
+                changed_before_edit.join(", ")
+            ));
+            return;
+        }
+        let spdx = self
+            .license_expression
+            .clone()
+            .or(previous_expression)
+            .map(|expression| expression.to_string())
+            .unwrap_or_default();
+        let (message, branch) = match op_mode.commit_action() {
+            Some(action) => (
+                program_settings
+                    .commit_message_template
+                    .replace("{action}", action)
+                    .replace("{spdx}", &spdx)
+                    .replace("{project}", &self.project_title),
+                program_settings
+                    .branch_name_template
+                    .replace("{action}", action)
+                    .replace("{spdx}", &branch_slug(&spdx))
+                    .replace("{project}", &self.project_title),
+            ),
+            None => (
+                program_settings.community_commit_message.replace("{project}", &self.project_title),
+                program_settings
+                    .branch_name_template
+                    .replace("{action}", "add")
+                    .replace("{spdx}", "community-files")
+                    .replace("{project}", &self.project_title),
+            ),
+        };
+        let options = CommitOptions {
+            message: &message,
+            signoff: program_settings.commit_signoff,
+            author: program_settings.commit_author.as_deref(),
+        };
+
+        if !program_settings.commit_on_branch {
+            match commit_files(&self.path, &self.touched_files, &options).await {
+                Ok(true) => {
+                    print_mode.normal_msg(format!("Committed \"{}\" in {}", message, self.project_title));
+                    self.touched_files.clear();
+                }
+                Ok(false) => print_mode.verbose_msg(format!("Nothing to commit in {}", self.project_title), None),
+                Err(error) => print_mode.error_msg(format!("{}: {}", self.project_title, error)),
+            }
+            return;
+        }
+
+        match self.commit_on_branch(program_settings, &branch, &options).await {
+            Ok(Some(patches)) => {
+                print_mode.normal_msg(format!(
+                    "Committed \"{}\" on branch {} in {}",
+                    message, branch, self.project_title
+                ));
+                for patch in patches {
+                    print_mode.verbose_msg(format!("Wrote {}", patch.display()), None);
+                }
+                self.touched_files.clear();
+            }
+            Ok(None) => print_mode.verbose_msg(format!("Nothing to commit in {}", self.project_title), None),
+            Err(error) => print_mode.error_msg(format!("{}: {}", self.project_title, error)),
         }
     }
 
+    /// Commits the touched files on a new branch, writes them as patches if `--patch-dir` is set,
+    /// and checks out the previous branch again, so the working tree looks like before.
+    ///
+    /// # Arguments
+    ///
+    /// * `program_settings` - The program settings, for the patch directory.
+    /// * `branch` - The name of the new branch.
+    /// * `options` - The message, signoff and author of the commit.
+    ///
+    /// # Returns
+    ///
+    /// The written patch files, or `None` if there was nothing to commit (the branch is deleted again).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the branch already exists, or if git fails.
+    async fn commit_on_branch(
+        &self,
+        program_settings: &ProgramSettings,
+        branch: &str,
+        options: &CommitOptions<'_>,
+    ) -> Result<Option<Vec<PathBuf>>, Box<dyn std::error::Error>> {
+        let previous_ref = current_ref(&self.path).await?;
+        create_branch(&self.path, branch).await?;
+        let committed = commit_files(&self.path, &self.touched_files, options).await;
+        if !matches!(committed, Ok(true)) {
+            // The changes stay in the working tree, only the empty branch goes away.
+            run_git(&self.path, &["checkout", "-q", &previous_ref]).await?;
+            run_git(&self.path, &["branch", "-q", "-D", branch]).await?;
+            return committed.map(|_| None);
+        }
+        run_git(&self.path, &["checkout", "-q", &previous_ref]).await?;
+
+        let Some(patch_directory) = &program_settings.patch_directory else {
+            return Ok(Some(Vec::new()));
+        };
+        let output_dir = Path::new(patch_directory).join(&self.project_title);
+        let range = format!("{}..{}", previous_ref, branch);
+        Ok(Some(format_patches(&self.path, &range, &output_dir).await?))
+    }
 
     pub async fn execute_user_action(
         &mut self,
@@ -393,49 +855,51 @@ impl GitDir {
         op_mode: &OperatingMode,
         licenses: Vec<GithubLicense>,
     ) {
+        if program_settings.commit_changes || program_settings.commit_on_branch {
+            self.changed_before_edit = changed_guarded_files(&self.path).await.unwrap_or_default();
+        }
         if op_mode == &OperatingMode::Unlicense {
             if let Some(unwrapped_license_path) = self.license_path.clone() {
-                if let Err(err) = tokio::fs::remove_file(unwrapped_license_path).await {
+                if let Err(err) = tokio::fs::remove_file(&unwrapped_license_path).await {
                     print_mode.error_msg(format!("{} occurred while deleting the license file in Unlicense mode", err))
                 } else {
+                    self.touch(unwrapped_license_path);
                     self.license_path = None;
                     self.license = None;
+                    self.license_expression = None;
+                    self.set_license_badge(program_settings, print_mode, None, false).await;
                 }
             }
             return;
         }
+        if op_mode == &OperatingMode::AddCommunityFiles {
+            self.add_community_files(program_settings, print_mode).await;
+            return;
+        }
 
-        if let Ok(uint) = GithubLicense::list_licenses_and_get_user_input(&licenses) {
+        if let Ok(uint) = GithubLicense::select_license(&licenses, &program_settings.preselected_license) {
             let user_choice = &licenses[uint];
             match op_mode {
                 OperatingMode::SetNewLicense => {
-                    self.write_license(program_settings, print_mode, user_choice, false)
+                    self.write_license(program_settings, print_mode, user_choice, &licenses, false)
                         .await
                 }
                 OperatingMode::AppendLicense => {
+                    self.rename_license_for_dual_layout(print_mode).await;
                     let mut license_path = self.license_path.clone().unwrap();
-                    if license_path.exists() {
-                        license_path.set_file_name(format!(
-                            "{}-{}",
-                            DEFAULT_LICENSE_FILE, user_choice.spdx_id
-                        ))
-                    }
+                    license_path.set_file_name(license_file_name(&user_choice.spdx_id));
                     self.license_path = Some(license_path);
-                    self.write_license(program_settings, print_mode, user_choice, true)
+                    self.write_license(program_settings, print_mode, user_choice, &licenses, true)
                         .await
                 }
                 OperatingMode::LicenseReplace => {
-                    if self.license_path.is_some()
-                        && tokio::fs::remove_file(self.license_path.clone().unwrap())
-                        .await
-                        .is_err()
-                    {
-                        print_mode
-                            .error_msg("Error occurred while deleting the current LICENSE file!");
+                    if !self.remove_license_files(print_mode, &licenses).await {
                         return;
                     }
-                    self.write_license(program_settings, print_mode, user_choice, false)
-                        .await
+                    self.write_license(program_settings, print_mode, user_choice, &licenses, false)
+                        .await;
+                    // The license section still names the replaced licenses.
+                    self.replace_in_readme(&self.license_section(program_settings, &licenses), print_mode).await;
                 }
                 _ => {}
             }
@@ -443,6 +907,104 @@ impl GitDir {
     }
 }
 
+/// Finds the license that matches the content of a license file.
+///
+/// An exception appended to the license is split off before, so it does not hide the license.
+///
+/// # Arguments
+///
+/// * `licenses` - All known licenses.
+/// * `content` - The content of the license file.
+///
+/// # Returns
+///
+/// The recognized license and the recognized exception.
+fn recognize_license(licenses: &[GithubLicense], content: &str) -> (Option<GithubLicense>, Option<&'static LicenseException>) {
+    let (content, exception) = split_exception(content);
+    let license = licenses
+        .iter()
+        .rfind(|available_license| is_alike(available_license.body.as_str(), content.as_str(), 60))
+        .cloned();
+    (license, exception)
+}
+
+/// Returns the part of a license file name after `LICENSE-`, like `MIT` for `LICENSE-MIT`.
+///
+/// Only text extensions are cut off, the dots of ids like `GPL-3.0-only` are kept.
+///
+/// # Arguments
+///
+/// * `path` - The path of the license file.
+fn license_file_suffix(path: &Path) -> String {
+    let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let name = LICENSE_FILE_EXTENSIONS
+        .iter()
+        .find_map(|extension| {
+            name.len()
+                .checked_sub(extension.len())
+                .filter(|at| name.is_char_boundary(*at) && name[*at..].eq_ignore_ascii_case(extension))
+                .map(|at| name[..at].to_string())
+        })
+        .unwrap_or(name);
+    name.split_once('-').map(|(_, suffix)| suffix.to_string()).unwrap_or_default()
+}
+
+/// Checks whether the suffix of a `LICENSE-*` file names a license, like `MIT`, `APACHE` or `BSD-3-Clause`.
+///
+/// Files like `LICENSE-THIRD-PARTY` or `LICENSE-FONTS` are not.
+///
+/// # Arguments
+///
+/// * `suffix` - The suffix of the file name, see `license_file_suffix`.
+/// * `licenses` - All known licenses.
+fn is_license_suffix(suffix: &str, licenses: &[GithubLicense]) -> bool {
+    let id = spdx_id_of_file_suffix(suffix);
+    let base = id.strip_suffix('+').unwrap_or(&id);
+    canonical_license_id(base).is_some()
+        || deprecated_replacement(&id).is_some()
+        || is_license_ref(&id)
+        || licenses.iter().any(|license| same_license(&license.spdx_id, &id))
+}
+
+/// Finds the license files of a dual-licensed repository, like `LICENSE-MIT` and `LICENSE-APACHE`.
+///
+/// # Arguments
+///
+/// * `path` - The path of the repository.
+/// * `licenses` - All known licenses, to recognize the content of the files.
+///
+/// # Returns
+///
+/// The license files sorted by name, with the recognized license and exception of each file.
+/// Files that are neither recognized nor named after a license are left out.
+async fn find_dual_licenses(
+    path: &str,
+    licenses: &[GithubLicense],
+) -> Vec<(PathBuf, Option<GithubLicense>, Option<&'static LicenseException>)> {
+    let mut files: Vec<PathBuf> = vec![];
+    if let Ok(mut entries) = tokio::fs::read_dir(path).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let name = entry.file_name().to_string_lossy().to_uppercase();
+            let is_file = tokio::fs::metadata(entry.path()).await.is_ok_and(|metadata| metadata.is_file());
+            if (name.starts_with("LICENSE-") || name.starts_with("LICENCE-")) && is_file {
+                files.push(entry.path());
+            }
+        }
+    }
+    files.sort();
+    let mut found: Vec<(PathBuf, Option<GithubLicense>, Option<&'static LicenseException>)> = vec![];
+    for file in files {
+        let (license, exception) = match tokio::fs::read_to_string(&file).await {
+            Ok(content) => recognize_license(licenses, &content),
+            Err(_) => (None, None),
+        };
+        if license.is_some() || is_license_suffix(&license_file_suffix(&file), licenses) {
+            found.push((file, license, exception));
+        }
+    }
+    found
+}
+
 impl Display for GitDir {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         // TODO: Make this even more prettier
@@ -453,3 +1015,129 @@ impl Display for GitDir {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+
+    /// A license with a body that needs no input of the user.
+    fn license(key: &str, spdx_id: &str, body: &str) -> GithubLicense {
+        GithubLicense { key: key.to_string(), name: spdx_id.to_string(), spdx_id: spdx_id.to_string(), body: body.to_string(), ..GithubLicense::default() }
+    }
+
+    /// Runs git in a repository and returns its output.
+    fn git(repository: &Path, args: &[&str]) -> String {
+        let output = Command::new("git").arg("-C").arg(repository).args(args).output().unwrap();
+        assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).to_string()
+    }
+
+    /// Creates a committed repository with the given files in the temp directory.
+    fn repository(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let repository = std::env::temp_dir().join(format!("license-me-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&repository);
+        std::fs::create_dir_all(&repository).unwrap();
+        git(&repository, &["init", "-q"]);
+        git(&repository, &["config", "user.name", "Test"]);
+        git(&repository, &["config", "user.email", "test@example.com"]);
+        for (name, content) in files {
+            std::fs::write(repository.join(name), content).unwrap();
+        }
+        git(&repository, &["add", "-A"]);
+        git(&repository, &["commit", "-q", "-m", "initial"]);
+        repository
+    }
+
+    static MIT: &str = "MIT License\n\nPermission is hereby granted, free of charge, to any person obtaining a copy of this software\n";
+    static APACHE: &str = "Apache License\nVersion 2.0, January 2004\n\nTERMS AND CONDITIONS FOR USE, REPRODUCTION, AND DISTRIBUTION\n";
+
+    /// The MIT and Apache-2.0 licenses.
+    fn licenses() -> Vec<GithubLicense> {
+        vec![license("mit", "MIT", MIT), license("apache-2.0", "Apache-2.0", APACHE)]
+    }
+
+    /// Reads a repository like the walker does.
+    async fn read_repository(repository: &Path, licenses: &[GithubLicense]) -> GitDir {
+        GitDir::init(format!("{}{}.git", repository.display(), MAIN_SEPARATOR), Some(licenses)).await
+    }
+
+    #[test]
+    fn cuts_only_text_extensions_off_license_file_names() {
+        assert_eq!(license_file_suffix(Path::new("LICENSE-MIT.txt")), "MIT");
+        assert_eq!(license_file_suffix(Path::new("LICENSE-APACHE.md")), "APACHE");
+        assert_eq!(license_file_suffix(Path::new("LICENSE-GPL-3.0-only")), "GPL-3.0-only");
+        assert!(is_license_suffix("GPL-3.0-only", &[]));
+        assert!(is_license_suffix("APACHE", &[]));
+        assert!(!is_license_suffix("THIRD-PARTY", &[]));
+        assert!(!is_license_suffix("FONTS", &[]));
+    }
+
+    #[tokio::test]
+    async fn ignores_license_files_that_name_no_license() {
+        let repository = repository(
+            "third-party",
+            &[("README.md", "# App\n"), ("LICENSE", MIT), ("LICENSE-THIRD-PARTY", "Bundled fonts and icons\n"), ("LICENSE-FONTS", "OFL\n")],
+        );
+        let dir = read_repository(&repository, &licenses()).await;
+        assert_eq!(dir.license_expression.map(|expression| expression.to_string()).as_deref(), Some("MIT"));
+        std::fs::remove_dir_all(&repository).unwrap();
+    }
+
+    #[tokio::test]
+    async fn counts_a_plain_license_beside_license_files() {
+        let repository = repository("plain-and-dual", &[("README.md", "# App\n"), ("LICENSE", MIT), ("LICENSE-APACHE", APACHE)]);
+        let dir = read_repository(&repository, &licenses()).await;
+        assert_eq!(dir.license_expression.map(|expression| expression.to_string()).as_deref(), Some("MIT OR Apache-2.0"));
+        std::fs::remove_dir_all(&repository).unwrap();
+    }
+
+    #[tokio::test]
+    async fn commits_the_removal_of_a_replaced_license() {
+        let (mit, apache, licenses) = (MIT, APACHE, licenses());
+        let repository = repository("replace", &[("README.md", "# App\n"), ("LICENSE-MIT", mit)]);
+        let settings = ProgramSettings {
+            commit_changes: true,
+            preselected_license: Some("Apache-2.0".to_string()),
+            ..ProgramSettings::default()
+        };
+        let mut print_mode = PrintMode::norm();
+
+        let mut dir = read_repository(&repository, &licenses).await;
+        let previous_expression = dir.license_expression.clone();
+        dir.execute_user_action(&settings, &mut print_mode, &OperatingMode::LicenseReplace, licenses).await;
+        dir.commit_changes(&settings, &mut print_mode, &OperatingMode::LicenseReplace, previous_expression).await;
+
+        assert!(!repository.join("LICENSE-MIT").exists());
+        assert_eq!(std::fs::read_to_string(repository.join("LICENSE")).unwrap(), apache);
+        assert_eq!(git(&repository, &["status", "--porcelain"]), "");
+        std::fs::remove_dir_all(&repository).unwrap();
+    }
+
+    #[tokio::test]
+    async fn replaces_all_licenses_of_a_dual_licensed_repository() {
+        let readme = "# App\n\n## License\n\nLicensed under either of\n\n* [Apache License 2.0](./LICENSE-APACHE)\n* [MIT License](./LICENSE-MIT)\n\nat your option.\n";
+        let repository = repository("replace-dual", &[("README.md", readme), ("LICENSE-MIT", MIT), ("LICENSE-APACHE", APACHE)]);
+        let settings = ProgramSettings {
+            commit_changes: true,
+            preselected_license: Some("MIT".to_string()),
+            ..ProgramSettings::default()
+        };
+        let mut print_mode = PrintMode::norm();
+
+        let mut dir = read_repository(&repository, &licenses()).await;
+        assert_eq!(dir.license_expression.as_ref().map(|expression| expression.to_string()).as_deref(), Some("Apache-2.0 OR MIT"));
+        let previous_expression = dir.license_expression.clone();
+        dir.execute_user_action(&settings, &mut print_mode, &OperatingMode::LicenseReplace, licenses()).await;
+        dir.commit_changes(&settings, &mut print_mode, &OperatingMode::LicenseReplace, previous_expression).await;
+
+        assert!(!repository.join("LICENSE-MIT").exists());
+        assert!(!repository.join("LICENSE-APACHE").exists());
+        assert_eq!(std::fs::read_to_string(repository.join("LICENSE")).unwrap(), MIT);
+        let readme = std::fs::read_to_string(repository.join("README.md")).unwrap();
+        assert!(!readme.contains("either of") && !readme.contains("LICENSE-APACHE"), "{}", readme);
+        assert_eq!(git(&repository, &["status", "--porcelain"]), "");
+        std::fs::remove_dir_all(&repository).unwrap();
+    }
+}
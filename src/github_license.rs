@@ -2,6 +2,7 @@ use chrono::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::read_input;
+use crate::spdx::same_license;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct MiniGithubLicense {
@@ -12,7 +13,29 @@ pub struct MiniGithubLicense {
     pub(crate) node_id: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+/// A license template as returned by the GitLab `/api/v4/templates/licenses` endpoint.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GitlabLicense {
+    pub(crate) key: String,
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) featured: bool,
+    #[serde(default)]
+    pub(crate) html_url: Option<String>,
+    #[serde(default)]
+    pub(crate) source_url: Option<String>,
+    #[serde(default)]
+    pub(crate) description: Option<String>,
+    #[serde(default)]
+    pub(crate) conditions: Vec<String>,
+    #[serde(default)]
+    pub(crate) permissions: Vec<String>,
+    #[serde(default)]
+    pub(crate) limitations: Vec<String>,
+    pub(crate) content: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Eq, PartialEq)]
 pub struct GithubLicense {
     pub(crate) key: String,
     pub(crate) name: String,
@@ -29,6 +52,53 @@ pub struct GithubLicense {
     pub(crate) featured: bool,
 }
 
+/// The SPDX ids of the license keys GitLab and GitHub share.
+static KNOWN_LICENSE_KEYS: [(&str, &str); 14] = [
+    ("agpl-3.0", "AGPL-3.0"),
+    ("apache-2.0", "Apache-2.0"),
+    ("bsd-2-clause", "BSD-2-Clause"),
+    ("bsd-3-clause", "BSD-3-Clause"),
+    ("bsl-1.0", "BSL-1.0"),
+    ("cc0-1.0", "CC0-1.0"),
+    ("epl-2.0", "EPL-2.0"),
+    ("gpl-2.0", "GPL-2.0"),
+    ("gpl-3.0", "GPL-3.0"),
+    ("lgpl-2.1", "LGPL-2.1"),
+    ("lgpl-3.0", "LGPL-3.0"),
+    ("mit", "MIT"),
+    ("mpl-2.0", "MPL-2.0"),
+    ("unlicense", "Unlicense"),
+];
+
+impl From<GitlabLicense> for GithubLicense {
+    /// Converts a GitLab license template into a `GithubLicense`.
+    ///
+    /// GitLab does not return SPDX ids, so the id is looked up from the license key.
+    /// Unknown keys become a `LicenseRef-<key>` id.
+    fn from(gitlab: GitlabLicense) -> Self {
+        let spdx_id = KNOWN_LICENSE_KEYS
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(&gitlab.key))
+            .map(|(_, spdx)| spdx.to_string())
+            .unwrap_or_else(|| format!("LicenseRef-{}", gitlab.key));
+        Self {
+            key: gitlab.key,
+            name: gitlab.name,
+            spdx_id,
+            url: gitlab.source_url.unwrap_or_default(),
+            node_id: String::new(),
+            html_url: gitlab.html_url.unwrap_or_default(),
+            description: gitlab.description.unwrap_or_default(),
+            implementation: String::new(),
+            permissions: gitlab.permissions,
+            conditions: gitlab.conditions,
+            limitations: gitlab.limitations,
+            body: gitlab.content,
+            featured: gitlab.featured,
+        }
+    }
+}
+
 impl GithubLicense {
     /// Sets the username and year in the `body` field of the struct.
     ///
@@ -56,6 +126,10 @@ impl GithubLicense {
 
     /// Returns a markdown license link.
     ///
+    /// # Arguments
+    ///
+    /// * `target` - The target of the link, like `./LICENSE` or the URL of the license.
+    ///
     /// # Returns
     ///
     /// A `String` representing the markdown license link.
@@ -67,11 +141,11 @@ impl GithubLicense {
     ///     spdx_id: "MIT".to_string(),
     ///     html_url: "https://opensource.org/licenses/MIT".to_string(),
     /// };
-    /// let link = license.get_markdown_license_link();
-    /// assert_eq!(link, "\n[MIT](https://opensource.org/licenses/MIT)");
+    /// let link = license.get_markdown_license_link("./LICENSE");
+    /// assert_eq!(link, "\n[MIT](./LICENSE)");
     /// ```
-    pub fn get_markdown_license_link(&self) -> String {
-        format!("\n[{}]({})", self.spdx_id, self.html_url)
+    pub fn get_markdown_license_link(&self, target: &str) -> String {
+        format!("\n[{}]({})", self.spdx_id, target)
     }
 
     /// Prints a list of licenses and gets user input for selecting a license.
@@ -120,4 +194,32 @@ impl GithubLicense {
             .for_each(|(c, l)| println!("[{}] {}", c + 1, l.name));
         Ok(read_input("Your Selection: ").parse::<usize>()? - 1)
     }
+
+    /// Selects a license, either the preselected one or by asking the user.
+    ///
+    /// # Arguments
+    ///
+    /// * `licenses` - A slice of `GithubLicense` structs representing the available licenses.
+    /// * `preselected` - The SPDX id given with `--license`, if any.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize, Box<dyn std::error::Error>>` - The index of the selected license.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the preselected license is not available, or if the user input is invalid.
+    pub fn select_license(
+        licenses: &[GithubLicense],
+        preselected: &Option<String>,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        if let Some(spdx_id) = preselected {
+            licenses
+                .iter()
+                .position(|license| same_license(&license.spdx_id, spdx_id))
+                .ok_or_else(|| Box::from(format!("The license \"{}\" is not available", spdx_id)))
+        } else {
+            GithubLicense::list_licenses_and_get_user_input(licenses)
+        }
+    }
 }
@@ -0,0 +1,254 @@
+use std::error::Error;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::github_license::GithubLicense;
+
+// A license badge is a line of Markdown images right below the first heading of a README,
+// like [![License: MIT](https://img.shields.io/badge/license-MIT-blue.svg)](https://...).
+// The line is found again by its "[![License" prefix, so it can be updated or removed.
+
+/// The start of every license badge, used to find the badge line in a README.
+static BADGE_PREFIX: &str = "[![License";
+
+/// Renders the badge URL pattern for a license.
+///
+/// The pattern can use `{{spdx_id}}`, `{{shields_spdx_id}}` (escaped for shields.io paths,
+/// where `-` has to be written as `--`) and `{{name}}`.
+///
+/// # Arguments
+///
+/// * `pattern` - The `license_badge_url` of the settings.
+/// * `license` - The license of the badge.
+pub fn badge_url(pattern: &str, license: &GithubLicense) -> String {
+    pattern
+        .replace("{{spdx_id}}", &license.spdx_id)
+        .replace(
+            "{{shields_spdx_id}}",
+            &license.spdx_id.replace('-', "--").replace('_', "__").replace(' ', "_"),
+        )
+        .replace("{{name}}", &license.name)
+}
+
+/// Returns the Markdown of the badge for a license.
+///
+/// # Arguments
+///
+/// * `pattern` - The `license_badge_url` of the settings.
+/// * `license` - The license of the badge.
+/// * `link` - The target of the badge, like the license file.
+pub fn badge_markdown(pattern: &str, license: &GithubLicense, link: &str) -> String {
+    format!(
+        "{}: {}]({})]({})",
+        BADGE_PREFIX,
+        license.spdx_id,
+        badge_url(pattern, license),
+        link
+    )
+}
+
+/// Inserts, replaces or removes the license badge of a README.
+///
+/// Only the license badges are changed, other badges on the same line (like CI or crates.io badges) are kept.
+/// A new badge line is inserted after the first heading, or at the top if there is no heading.
+/// Fenced code blocks are skipped, their `#` lines are no headings.
+///
+/// # Arguments
+///
+/// * `readme_path` - The path of the README.
+/// * `badge` - The new badge, or `None` to remove the license badges.
+/// * `append` - Adds the badge to the existing badges, instead of replacing them (for multiple licenses).
+///
+/// # Returns
+///
+/// Whether the README changed.
+///
+/// # Errors
+///
+/// Returns an error if the README cannot be read or written.
+pub async fn update_badge(readme_path: &Path, badge: Option<&str>, append: bool) -> Result<bool, Box<dyn Error>> {
+    let content = tokio::fs::read_to_string(readme_path).await?;
+    let new_content = set_badge(&content, badge, append);
+    if new_content == content {
+        return Ok(false);
+    }
+    tokio::fs::write(readme_path, new_content).await?;
+    Ok(true)
+}
+
+/// Inserts, replaces or removes the license badge in the content of a README, see `update_badge`.
+///
+/// # Arguments
+///
+/// * `content` - The content of the README.
+/// * `badge` - The new badge, or `None` to remove the license badges.
+/// * `append` - Adds the badge to the existing badges, instead of replacing them.
+fn set_badge(content: &str, badge: Option<&str>, append: bool) -> String {
+    let mut lines = content.lines().map(str::to_string).collect::<Vec<String>>();
+    let outside_fences = lines_outside_fences(&lines);
+    let badge_line = outside_fences
+        .iter()
+        .copied()
+        .find(|index| !badge_tokens(&lines[*index]).is_empty());
+
+    match (badge, badge_line) {
+        (None, None) => return content.to_string(),
+        (None, Some(index)) => {
+            let remaining = remove_tokens(&lines[index], &badge_tokens(&lines[index]));
+            if remaining.trim().is_empty() {
+                lines.remove(index);
+                // Remove the blank line, that separated the badge from the heading.
+                if index > 0 && index < lines.len() && lines[index].trim().is_empty() && lines[index - 1].trim().is_empty() {
+                    lines.remove(index);
+                }
+            } else {
+                lines[index] = remaining;
+            }
+        }
+        (Some(badge), Some(index)) => {
+            let line = &lines[index];
+            let tokens = badge_tokens(line);
+            if !append {
+                // The first license badge is replaced, the ones of other licenses go away.
+                let first = tokens[0].clone();
+                let rest = remove_tokens(&line[first.end..], &tokens[1..].iter().map(|token| token.start - first.end..token.end - first.end).collect::<Vec<_>>());
+                lines[index] = format!("{}{}{}", &line[..first.start], badge, rest);
+            } else if !line.contains(badge) {
+                let last = tokens[tokens.len() - 1].end;
+                lines[index] = format!("{} {}{}", &line[..last], badge, &line[last..]);
+            }
+        }
+        (Some(badge), None) => match outside_fences.iter().copied().find(|index| lines[*index].starts_with('#')) {
+            Some(heading) => {
+                let mut inserted = vec![String::new(), badge.to_string()];
+                if lines.get(heading + 1).is_some_and(|line| !line.trim().is_empty()) {
+                    inserted.push(String::new());
+                }
+                lines.splice(heading + 1..heading + 1, inserted);
+            }
+            None => {
+                lines.splice(0..0, [badge.to_string(), String::new()]);
+            }
+        },
+    }
+
+    let mut new_content = lines.join("\n");
+    if content.ends_with('\n') {
+        new_content.push('\n');
+    }
+    new_content
+}
+
+/// Returns the indices of the lines, that are not inside a fenced code block.
+///
+/// # Arguments
+///
+/// * `lines` - The lines of the README.
+fn lines_outside_fences(lines: &[String]) -> Vec<usize> {
+    let mut fence: Option<&str> = None;
+    let mut outside: Vec<usize> = vec![];
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|marker| trimmed.starts_with(marker));
+        match (fence, marker) {
+            (None, Some(marker)) => fence = Some(marker),
+            (Some(open), Some(marker)) if open == marker => fence = None,
+            (None, None) => outside.push(index),
+            _ => {}
+        }
+    }
+    outside
+}
+
+/// Returns the byte ranges of the license badges in a line, like `[![License: MIT](...)](...)`.
+///
+/// # Arguments
+///
+/// * `line` - A line of the README.
+fn badge_tokens(line: &str) -> Vec<Range<usize>> {
+    let mut tokens: Vec<Range<usize>> = vec![];
+    let mut offset = 0;
+    while let Some(found) = line[offset..].find(BADGE_PREFIX) {
+        let start = offset + found;
+        // The image is closed by "](url)", the link around it by "](target)".
+        let end = line[start..]
+            .find(")](")
+            .and_then(|image_end| line[start + image_end + 3..].find(')').map(|link_end| start + image_end + 3 + link_end + 1));
+        match end {
+            Some(end) => {
+                tokens.push(start..end);
+                offset = end;
+            }
+            None => break,
+        }
+    }
+    tokens
+}
+
+/// Removes byte ranges from a line, together with the space that separated them from the next badge.
+///
+/// # Arguments
+///
+/// * `line` - The line.
+/// * `tokens` - The sorted ranges to remove.
+fn remove_tokens(line: &str, tokens: &[Range<usize>]) -> String {
+    let mut remaining = String::new();
+    let mut offset = 0;
+    for token in tokens {
+        remaining.push_str(&line[offset..token.start]);
+        offset = token.end;
+        if line[offset..].starts_with(' ') {
+            offset += 1;
+        }
+    }
+    remaining.push_str(&line[offset..]);
+    remaining.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static MIT: &str = "[![License: MIT](https://img.shields.io/badge/License-MIT-yellow.svg)](./LICENSE)";
+    static APACHE: &str = "[![License: Apache-2.0](https://img.shields.io/badge/License-Apache--2.0-blue.svg)](./LICENSE-APACHE)";
+    static CI: &str = "[![CI](https://example.com/ci.svg)](https://example.com/ci)";
+    static CRATE: &str = "[![Crates.io](https://img.shields.io/crates/v/demo.svg)](https://crates.io/crates/demo)";
+
+    #[test]
+    fn replaces_only_the_license_badge() {
+        let content = format!("# Demo\n\n{} {} {}\n", CI, MIT, CRATE);
+        assert_eq!(set_badge(&content, Some(APACHE), false), format!("# Demo\n\n{} {} {}\n", CI, APACHE, CRATE));
+    }
+
+    #[test]
+    fn replaces_all_license_badges_with_one() {
+        let content = format!("# Demo\n\n{} {} {} {}\n", CI, MIT, APACHE, CRATE);
+        assert_eq!(set_badge(&content, Some(MIT), false), format!("# Demo\n\n{} {} {}\n", CI, MIT, CRATE));
+    }
+
+    #[test]
+    fn appends_after_the_last_license_badge() {
+        let content = format!("# Demo\n\n{} {} {}\n", CI, MIT, CRATE);
+        assert_eq!(set_badge(&content, Some(APACHE), true), format!("# Demo\n\n{} {} {} {}\n", CI, MIT, APACHE, CRATE));
+        assert_eq!(set_badge(&format!("# Demo\n\n{}\n", MIT), Some(MIT), true), format!("# Demo\n\n{}\n", MIT));
+    }
+
+    #[test]
+    fn removes_only_the_license_badges() {
+        let content = format!("# Demo\n\n{} {} {}\n", CI, MIT, CRATE);
+        assert_eq!(set_badge(&content, None, false), format!("# Demo\n\n{} {}\n", CI, CRATE));
+        let content = format!("# Demo\n\n{} {}\n\nText\n", MIT, APACHE);
+        assert_eq!(set_badge(&content, None, false), "# Demo\n\nText\n");
+    }
+
+    #[test]
+    fn skips_fenced_code_blocks() {
+        let content = format!("```sh\n# comment\n{}\n```\n\n# Demo\n\nText\n", MIT);
+        assert_eq!(
+            set_badge(&content, Some(APACHE), false),
+            format!("```sh\n# comment\n{}\n```\n\n# Demo\n\n{}\n\nText\n", MIT, APACHE)
+        );
+        let content = "~~~\n# not a heading\n~~~\nText\n";
+        assert_eq!(set_badge(content, Some(MIT), false), format!("{}\n\n{}", MIT, content));
+    }
+}
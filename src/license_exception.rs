@@ -0,0 +1,112 @@
+use std::path::PathBuf;
+
+use crate::spdx::canonical_exception_id;
+
+// License exceptions grant additional permissions on top of a license, written in SPDX as
+// "GPL-2.0-only WITH Classpath-exception-2.0". Their text is either appended to the license file,
+// or shipped in a file beside it (like "CLASSPATH-EXCEPTION" or "LICENSE.exception").
+
+/// A license exception, with the text that is written next to the license.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LicenseException {
+    /// The SPDX id of the exception.
+    pub(crate) id: &'static str,
+    /// The full name of the exception.
+    pub(crate) name: &'static str,
+    /// A phrase only the text of this exception contains, used to recognize it.
+    marker: &'static str,
+    /// The text of the exception.
+    pub(crate) text: &'static str,
+}
+
+/// The exceptions that can be recognized and written.
+static EXCEPTIONS: [LicenseException; 2] = [
+    LicenseException {
+        id: "Classpath-exception-2.0",
+        name: "Classpath exception 2.0",
+        marker: "As a special exception, the copyright holders of this library give you permission to link this library with independent modules",
+        text: include_str!("../resources/exceptions/Classpath-exception-2.0.txt"),
+    },
+    LicenseException {
+        id: "LLVM-exception",
+        name: "LLVM Exception",
+        marker: "LLVM Exceptions to the Apache 2.0 License",
+        text: include_str!("../resources/exceptions/LLVM-exception.txt"),
+    },
+];
+
+/// How many words of the start of an exception text are used to find where it begins.
+static START_WORDS: usize = 8;
+
+impl LicenseException {
+    /// Returns the exception with the given SPDX id.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The SPDX id of the exception, in any case.
+    pub fn find(id: &str) -> Option<&'static LicenseException> {
+        let id = canonical_exception_id(id).unwrap_or(id);
+        EXCEPTIONS.iter().find(|exception| exception.id.eq_ignore_ascii_case(id))
+    }
+
+    /// Returns the exception text to append to a license file.
+    pub fn appendix(&self) -> String {
+        format!("\n\n{}", self.text)
+    }
+}
+
+/// Collapses all whitespace to single spaces, so wrapped lines do not matter.
+///
+/// # Arguments
+///
+/// * `text` - The text to normalize.
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Finds an exception in the text of a license file, and splits it off.
+///
+/// # Arguments
+///
+/// * `content` - The content of the license file.
+///
+/// # Returns
+///
+/// The text of the license without the exception (with collapsed whitespace, if an exception was found),
+/// and the recognized exception.
+pub fn split_exception(content: &str) -> (String, Option<&'static LicenseException>) {
+    let collapsed = collapse_whitespace(content);
+    for exception in EXCEPTIONS.iter() {
+        let Some(marker_at) = collapsed.find(exception.marker) else {
+            continue;
+        };
+        let start = exception.text.split_whitespace().take(START_WORDS).collect::<Vec<&str>>().join(" ");
+        let cut_at = collapsed.find(&start).filter(|at| *at <= marker_at).unwrap_or(marker_at);
+        return (collapsed[..cut_at].trim_end().to_string(), Some(exception));
+    }
+    (content.to_string(), None)
+}
+
+/// Finds an exception shipped in its own file in the root of a repository, like `CLASSPATH-EXCEPTION`.
+///
+/// # Arguments
+///
+/// * `path` - The path of the repository.
+///
+/// # Returns
+///
+/// The path of the file and the recognized exception.
+pub async fn find_exception_file(path: &str) -> Option<(PathBuf, &'static LicenseException)> {
+    let mut entries = tokio::fs::read_dir(path).await.ok()?;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if !entry.file_name().to_string_lossy().to_uppercase().contains("EXCEPTION") {
+            continue;
+        }
+        if let Ok(content) = tokio::fs::read_to_string(entry.path()).await {
+            if let (_, Some(exception)) = split_exception(&content) {
+                return Some((entry.path(), exception));
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,434 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::spdx::{canonical_exception_id, canonical_license_id, deprecated_replacement, is_idstring, is_license_ref};
+
+// A repository can be licensed under more than one license, written as SPDX expression like
+// "MIT OR Apache-2.0" (the user chooses one), "MIT AND BSD-3-Clause" (both apply) or
+// "GPL-2.0-or-later WITH Classpath-exception-2.0" (a license with an exception).
+// AND binds stronger than OR, parentheses group sub-expressions.
+
+/// File names of licenses in dual-licensed repositories, following the Rust ecosystem convention.
+static LICENSE_FILE_SUFFIXES: [(&str, &str); 2] = [("Apache-2.0", "APACHE"), ("MIT", "MIT")];
+
+/// The license of a repository, as SPDX license expression.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LicenseExpression {
+    /// A single license, optionally with an exception.
+    License { id: String, exception: Option<String> },
+    /// All licenses apply.
+    And(Vec<LicenseExpression>),
+    /// The user can choose one of the licenses.
+    Or(Vec<LicenseExpression>),
+}
+
+impl LicenseExpression {
+    /// Creates the expression of a single license.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The SPDX id of the license.
+    pub fn single(id: &str) -> Self {
+        LicenseExpression::License { id: id.to_string(), exception: None }
+    }
+
+    /// Parses a SPDX license expression.
+    ///
+    /// # Arguments
+    ///
+    /// * `expression` - The expression, like `(MIT OR Apache-2.0) AND BSD-3-Clause`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the expression is empty, has unbalanced parentheses, or an operator without operand.
+    pub fn parse(expression: &str) -> Result<Self, Box<dyn Error>> {
+        let tokens = tokenize(expression);
+        if tokens.is_empty() {
+            return Err(Box::from("The license expression is empty"));
+        }
+        let mut position = 0;
+        let parsed = parse_or(&tokens, &mut position)?;
+        if position < tokens.len() {
+            return Err(Box::from(format!("Unexpected \"{}\" in the license expression \"{}\"", tokens[position], expression)));
+        }
+        Ok(parsed)
+    }
+
+    /// Combines this expression with another license the user can choose instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The other expression.
+    pub fn or(self, other: LicenseExpression) -> Self {
+        let mut alternatives = match self {
+            LicenseExpression::Or(alternatives) => alternatives,
+            expression => vec![expression],
+        };
+        let more = match other {
+            LicenseExpression::Or(more) => more,
+            expression => vec![expression],
+        };
+        for expression in more {
+            if !alternatives.contains(&expression) {
+                alternatives.push(expression);
+            }
+        }
+        if alternatives.len() == 1 {
+            alternatives.remove(0)
+        } else {
+            LicenseExpression::Or(alternatives)
+        }
+    }
+
+    /// Validates the expression against the SPDX license list and normalizes it.
+    ///
+    /// Ids get their canonical case (`mit` becomes `MIT`), deprecated ids are replaced
+    /// (`GPL-2.0+` becomes `GPL-2.0-or-later`) and nested `AND`/`OR` of the same kind are flattened.
+    /// `LicenseRef-*` ids, and well-formed ids that are not in the built-in part of the SPDX list
+    /// (like `AFL-2.1`), are kept as they are, see `unknown_ids`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for ids with characters SPDX does not allow.
+    pub fn normalize(&self) -> Result<Self, Box<dyn Error>> {
+        match self {
+            LicenseExpression::License { id, exception } => {
+                let exception = match exception {
+                    Some(exception) => match canonical_exception_id(exception) {
+                        Some(canonical) => Some(canonical.to_string()),
+                        None if is_idstring(exception) => Some(exception.clone()),
+                        None => return Err(Box::from(format!("\"{}\" is not a valid SPDX license exception id", exception))),
+                    },
+                    None => None,
+                };
+                normalize_license_id(id, exception)
+            }
+            LicenseExpression::And(parts) | LicenseExpression::Or(parts) => {
+                let mut normalized: Vec<LicenseExpression> = vec![];
+                for part in parts {
+                    match (self, part.normalize()?) {
+                        (LicenseExpression::And(_), LicenseExpression::And(inner))
+                        | (LicenseExpression::Or(_), LicenseExpression::Or(inner)) => normalized.extend(inner),
+                        (_, part) => normalized.push(part),
+                    }
+                }
+                Ok(match self {
+                    LicenseExpression::And(_) => LicenseExpression::And(normalized),
+                    _ => LicenseExpression::Or(normalized),
+                })
+            }
+        }
+    }
+
+    /// Returns the SPDX ids of all licenses in the expression, without exceptions.
+    pub fn license_ids(&self) -> Vec<String> {
+        match self {
+            LicenseExpression::License { id, .. } => vec![id.clone()],
+            LicenseExpression::And(parts) | LicenseExpression::Or(parts) => {
+                parts.iter().flat_map(|part| part.license_ids()).collect()
+            }
+        }
+    }
+
+    /// Returns the license and exception ids of the expression, that are neither on the built-in
+    /// SPDX list nor `LicenseRef-*` ids. They may still be valid ids of the full SPDX list.
+    pub fn unknown_ids(&self) -> Vec<String> {
+        match self {
+            LicenseExpression::License { id, exception } => {
+                let mut unknown = vec![];
+                let base = id.strip_suffix('+').unwrap_or(id);
+                if !is_license_ref(id) && canonical_license_id(base).is_none() {
+                    unknown.push(id.clone());
+                }
+                if let Some(exception) = exception.as_ref().filter(|exception| canonical_exception_id(exception).is_none()) {
+                    unknown.push(exception.clone());
+                }
+                unknown
+            }
+            LicenseExpression::And(parts) | LicenseExpression::Or(parts) => {
+                parts.iter().flat_map(|part| part.unknown_ids()).collect()
+            }
+        }
+    }
+
+    /// Checks whether any license of the expression has an exception.
+    pub fn has_exception(&self) -> bool {
+        match self {
+            LicenseExpression::License { exception, .. } => exception.is_some(),
+            LicenseExpression::And(parts) | LicenseExpression::Or(parts) => parts.iter().any(|part| part.has_exception()),
+        }
+    }
+
+    /// Returns the license parts of the expression, if it is a single license or a flat `OR`/`AND`.
+    fn flat_parts(&self) -> Option<Vec<(&str, &Option<String>)>> {
+        match self {
+            LicenseExpression::License { .. } => self.as_license().map(|part| vec![part]),
+            LicenseExpression::And(parts) | LicenseExpression::Or(parts) => {
+                parts.iter().map(LicenseExpression::as_license).collect()
+            }
+        }
+    }
+
+    /// Returns the id and exception, if the expression is a single license.
+    fn as_license(&self) -> Option<(&str, &Option<String>)> {
+        match self {
+            LicenseExpression::License { id, exception } => Some((id.as_str(), exception)),
+            _ => None,
+        }
+    }
+
+    /// Renders the license section of a README.
+    ///
+    /// # Arguments
+    ///
+    /// * `describe` - Renders one license as Markdown, like `MIT License ([LICENSE-MIT](./LICENSE-MIT))`.
+    ///
+    /// # Returns
+    ///
+    /// The text of the section, like "Licensed under either of ... at your option.".
+    pub fn readme_wording(&self, describe: impl Fn(&str) -> String) -> String {
+        let item = |(id, exception): (&str, &Option<String>)| match exception {
+            Some(exception) => format!("{} with the {}", describe(id), exception),
+            None => describe(id),
+        };
+        let list = |parts: Vec<(&str, &Option<String>)>| {
+            parts.into_iter().map(|part| format!("* {}", item(part))).collect::<Vec<String>>().join("\n")
+        };
+        match (self, self.flat_parts()) {
+            (LicenseExpression::License { .. }, Some(mut parts)) => format!("Licensed under {}.", item(parts.remove(0))),
+            (LicenseExpression::Or(_), Some(parts)) => format!("Licensed under either of\n\n{}\n\nat your option.", list(parts)),
+            (LicenseExpression::And(_), Some(parts)) => format!("Licensed under all of\n\n{}", list(parts)),
+            _ => {
+                let ids = self.license_ids();
+                let parts = ids.iter().map(|id| (id.as_str(), &None)).collect();
+                format!("Licensed under `{}`, using these licenses:\n\n{}", self, list(parts))
+            }
+        }
+    }
+}
+
+impl Display for LicenseExpression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LicenseExpression::License { id, exception: Some(exception) } => write!(f, "{} WITH {}", id, exception),
+            LicenseExpression::License { id, exception: None } => write!(f, "{}", id),
+            LicenseExpression::And(parts) => {
+                let rendered = parts
+                    .iter()
+                    .map(|part| match part {
+                        LicenseExpression::Or(_) => format!("({})", part),
+                        _ => part.to_string(),
+                    })
+                    .collect::<Vec<String>>();
+                write!(f, "{}", rendered.join(" AND "))
+            }
+            LicenseExpression::Or(parts) => {
+                write!(f, "{}", parts.iter().map(|part| part.to_string()).collect::<Vec<String>>().join(" OR "))
+            }
+        }
+    }
+}
+
+/// Returns the file name of a license in a dual-licensed repository, like `LICENSE-MIT` or `LICENSE-APACHE`.
+///
+/// # Arguments
+///
+/// * `spdx_id` - The SPDX id of the license.
+pub fn license_file_name(spdx_id: &str) -> String {
+    let suffix = LICENSE_FILE_SUFFIXES
+        .iter()
+        .find(|(id, _)| id.eq_ignore_ascii_case(spdx_id))
+        .map(|(_, suffix)| suffix.to_string())
+        .unwrap_or_else(|| spdx_id.to_string());
+    format!("LICENSE-{}", suffix)
+}
+
+/// Returns the SPDX id for the suffix of a license file name, the reverse of `license_file_name`.
+///
+/// # Arguments
+///
+/// * `suffix` - The suffix after `LICENSE-`, like `APACHE`.
+pub fn spdx_id_of_file_suffix(suffix: &str) -> String {
+    LICENSE_FILE_SUFFIXES
+        .iter()
+        .find(|(_, known)| known.eq_ignore_ascii_case(suffix))
+        .map(|(id, _)| id.to_string())
+        .unwrap_or_else(|| suffix.to_string())
+}
+
+/// Normalizes a single license id, see `LicenseExpression::normalize`.
+///
+/// # Arguments
+///
+/// * `id` - The license id, optionally with the `+` ("or later") suffix.
+/// * `exception` - The already normalized exception of the license.
+fn normalize_license_id(id: &str, exception: Option<String>) -> Result<LicenseExpression, Box<dyn Error>> {
+    if is_license_ref(id) {
+        return Ok(LicenseExpression::License { id: id.to_string(), exception });
+    }
+    if let Some(replacement) = deprecated_replacement(id) {
+        return match LicenseExpression::parse(replacement)? {
+            LicenseExpression::License { id, exception: replaced } => {
+                Ok(LicenseExpression::License { id, exception: exception.or(replaced) })
+            }
+            expression => Ok(expression),
+        };
+    }
+    let (base, or_later) = match id.strip_suffix('+') {
+        Some(base) => (base, "+"),
+        None => (id, ""),
+    };
+    match canonical_license_id(base) {
+        Some(canonical) => Ok(LicenseExpression::License { id: format!("{}{}", canonical, or_later), exception }),
+        // The built-in list is only a part of the SPDX list, so other well-formed ids are kept.
+        None if is_idstring(base) && !["LicenseRef-", "DocumentRef-"].iter().any(|prefix| base.starts_with(prefix)) => Ok(LicenseExpression::License { id: id.to_string(), exception }),
+        None => Err(Box::from(format!(
+            "\"{}\" is not a valid SPDX license id, use \"LicenseRef-<name>\" with letters, digits, \"-\" and \".\" for other licenses",
+            id
+        ))),
+    }
+}
+
+/// Splits an expression into parentheses and words.
+///
+/// # Arguments
+///
+/// * `expression` - The SPDX expression.
+fn tokenize(expression: &str) -> Vec<String> {
+    expression
+        .replace('(', " ( ")
+        .replace(')', " ) ")
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Checks whether a token is the given operator, ignoring its case.
+fn is_operator(token: Option<&String>, operator: &str) -> bool {
+    token.is_some_and(|token| token.eq_ignore_ascii_case(operator))
+}
+
+/// Parses `and-expression ("OR" and-expression)*`.
+fn parse_or(tokens: &[String], position: &mut usize) -> Result<LicenseExpression, Box<dyn Error>> {
+    let mut alternatives = vec![parse_and(tokens, position)?];
+    while is_operator(tokens.get(*position), "OR") {
+        *position += 1;
+        alternatives.push(parse_and(tokens, position)?);
+    }
+    Ok(if alternatives.len() == 1 { alternatives.remove(0) } else { LicenseExpression::Or(alternatives) })
+}
+
+/// Parses `primary ("AND" primary)*`.
+fn parse_and(tokens: &[String], position: &mut usize) -> Result<LicenseExpression, Box<dyn Error>> {
+    let mut parts = vec![parse_primary(tokens, position)?];
+    while is_operator(tokens.get(*position), "AND") {
+        *position += 1;
+        parts.push(parse_primary(tokens, position)?);
+    }
+    Ok(if parts.len() == 1 { parts.remove(0) } else { LicenseExpression::And(parts) })
+}
+
+/// Parses `"(" or-expression ")"` or `license-id ["WITH" exception-id]`.
+fn parse_primary(tokens: &[String], position: &mut usize) -> Result<LicenseExpression, Box<dyn Error>> {
+    let token = tokens
+        .get(*position)
+        .ok_or_else(|| Box::<dyn Error>::from("The license expression ends with an operator"))?;
+    *position += 1;
+    if token == "(" {
+        let inner = parse_or(tokens, position)?;
+        if tokens.get(*position).map(String::as_str) != Some(")") {
+            return Err(Box::from("Missing \")\" in the license expression"));
+        }
+        *position += 1;
+        return Ok(inner);
+    }
+    if token == ")" || ["AND", "OR", "WITH"].iter().any(|op| token.eq_ignore_ascii_case(op)) {
+        return Err(Box::from(format!("Expected a license id, found \"{}\"", token)));
+    }
+    let exception = if is_operator(tokens.get(*position), "WITH") {
+        *position += 1;
+        let exception = tokens
+            .get(*position)
+            .filter(|exception| !["(", ")"].contains(&exception.as_str()))
+            .ok_or_else(|| Box::<dyn Error>::from("\"WITH\" needs an exception id"))?;
+        *position += 1;
+        Some(exception.clone())
+    } else {
+        None
+    };
+    Ok(LicenseExpression::License { id: token.clone(), exception })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses and normalizes an expression, then renders it again.
+    fn normalized(expression: &str) -> String {
+        LicenseExpression::parse(expression).and_then(|expression| expression.normalize()).unwrap().to_string()
+    }
+
+    #[test]
+    fn parses_and_renders_the_examples() {
+        for expression in [
+            "(MIT OR Apache-2.0) AND BSD-3-Clause",
+            "GPL-2.0-or-later WITH Classpath-exception-2.0",
+            "LicenseRef-ACME-Proprietary",
+            "DocumentRef-spdx-tool-1.2:LicenseRef-MIT-Style-2",
+            "MIT OR Apache-2.0",
+        ] {
+            assert_eq!(normalized(expression), expression);
+        }
+    }
+
+    #[test]
+    fn and_binds_stronger_than_or() {
+        assert_eq!(
+            LicenseExpression::parse("MIT OR Apache-2.0 AND BSD-3-Clause").unwrap(),
+            LicenseExpression::Or(vec![
+                LicenseExpression::single("MIT"),
+                LicenseExpression::And(vec![LicenseExpression::single("Apache-2.0"), LicenseExpression::single("BSD-3-Clause")]),
+            ])
+        );
+        assert_eq!(normalized("MIT OR (Apache-2.0 AND BSD-3-Clause)"), "MIT OR Apache-2.0 AND BSD-3-Clause");
+        assert_eq!(normalized("(MIT OR (Apache-2.0 OR Zlib))"), "MIT OR Apache-2.0 OR Zlib");
+    }
+
+    #[test]
+    fn normalizes_the_case_of_ids_and_operators() {
+        assert_eq!(normalized("mit or apache-2.0"), "MIT OR Apache-2.0");
+        assert_eq!(normalized("gpl-2.0-or-later with classpath-exception-2.0"), "GPL-2.0-or-later WITH Classpath-exception-2.0");
+    }
+
+    #[test]
+    fn replaces_deprecated_ids() {
+        assert_eq!(normalized("GPL-2.0+"), "GPL-2.0-or-later");
+        assert_eq!(normalized("GPL-3.0"), "GPL-3.0-only");
+        assert_eq!(normalized("lgpl-2.1+ OR MIT"), "LGPL-2.1-or-later OR MIT");
+        assert_eq!(normalized("GPL-2.0-with-classpath-exception"), "GPL-2.0-only WITH Classpath-exception-2.0");
+        assert_eq!(normalized("wxWindows AND MIT"), "LGPL-2.0-or-later WITH WxWindows-exception-3.1 AND MIT");
+    }
+
+    #[test]
+    fn keeps_well_formed_ids_outside_the_built_in_list() {
+        assert_eq!(normalized("AFL-2.1 OR CERN-OHL-S-2.0"), "AFL-2.1 OR CERN-OHL-S-2.0");
+        assert_eq!(normalized("Beerware"), "Beerware");
+        let expression = LicenseExpression::parse("MIT AND Beerware WITH Some-exception-1.0 AND LicenseRef-ACME")
+            .and_then(|expression| expression.normalize())
+            .unwrap();
+        assert_eq!(expression.unknown_ids(), vec!["Beerware".to_string(), "Some-exception-1.0".to_string()]);
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for expression in ["", "MIT OR", "(MIT", "MIT AND AND Zlib", "MIT WITH", "MIT_2.0", "LicenseRef-"] {
+            assert!(
+                LicenseExpression::parse(expression).and_then(|expression| expression.normalize()).is_err(),
+                "{} should be rejected",
+                expression
+            );
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+use crate::github_license::GithubLicense;
+
+/// The base URL of the SPDX license pages.
+static SPDX_LICENSE_URL: &str = "https://spdx.org/licenses/";
+
+/// How the README links to the license.
+///
+/// Relative links work on GitHub, GitLab and on mirrors without internet access,
+/// the external and SPDX links point at a description of the license.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LicenseLinkStyle {
+    /// Links the license file in the repository, like `./LICENSE` or `./LICENSE-MIT`.
+    Relative,
+    /// Links the `html_url` of the license, like the page on choosealicense.com.
+    #[default]
+    External,
+    /// Links the page of the license on spdx.org.
+    Spdx,
+}
+
+impl LicenseLinkStyle {
+    /// Returns the target of a license link in this style.
+    ///
+    /// # Arguments
+    ///
+    /// * `license` - The linked license.
+    /// * `license_file` - The file name of the license in the repository, like `LICENSE-MIT`.
+    ///
+    /// # Returns
+    ///
+    /// The target of the link. External links fall back to the SPDX page, if the license has no `html_url`.
+    pub fn target(&self, license: &GithubLicense, license_file: &str) -> String {
+        match self {
+            LicenseLinkStyle::Relative => format!("./{}", license_file),
+            LicenseLinkStyle::External if !license.html_url.is_empty() => license.html_url.clone(),
+            LicenseLinkStyle::External | LicenseLinkStyle::Spdx => {
+                format!("{}{}.html", SPDX_LICENSE_URL, license.spdx_id)
+            }
+        }
+    }
+}
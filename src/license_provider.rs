@@ -0,0 +1,519 @@
+use std::error::Error;
+use std::time::{Duration, SystemTime};
+
+use futures::future::LocalBoxFuture;
+
+use crate::api_communicator::{get_all_licenses, get_gitlab_license, get_gitlab_licenses, get_license_by_key};
+use crate::api_error::ApiError;
+use crate::custom_license::load_custom_licenses;
+use crate::github_license::GithubLicense;
+use crate::license_source::LicenseSource;
+use crate::output_printer::PrintMode;
+use crate::settings_file::ProgramSettings;
+use crate::spdx::{deprecated_replacement, same_license};
+use crate::spdx_license_list::load_spdx_licenses;
+
+// Every backend that can deliver licenses implements the LicenseProvider trait.
+// Remote providers keep a cache file next to the settings file, so the API is only
+// asked again when the cache is missing, older than LICENSE_CACHE_MAX_AGE or a refresh is requested (--refresh-licenses).
+
+static EMBEDDED_SNAPSHOT: &str = include_str!("../resources/licenses_snapshot.json");
+
+/// The age after which the cache of a remote provider is downloaded again, a week.
+static LICENSE_CACHE_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// A backend that delivers licenses.
+pub trait LicenseProvider {
+    /// Returns a short, human readable name of the provider.
+    fn name(&self) -> String;
+
+    /// Lists all licenses of the provider.
+    ///
+    /// # Arguments
+    ///
+    /// * `print_mode` - A mutable reference to the `PrintMode` for messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the provider could not deliver its licenses.
+    fn list<'a>(&'a self, print_mode: &'a mut PrintMode) -> LocalBoxFuture<'a, Result<Vec<GithubLicense>, Box<dyn Error>>>;
+
+    /// Fetches a single license by its SPDX id.
+    ///
+    /// The default implementation lists all licenses and searches for the id.
+    ///
+    /// # Arguments
+    ///
+    /// * `spdx_id` - The SPDX id of the license, compared case-insensitive.
+    /// * `print_mode` - A mutable reference to the `PrintMode` for messages.
+    ///
+    /// # Returns
+    ///
+    /// The license, or `None` if the provider does not know it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the provider could not be asked, like a network error of an API.
+    fn fetch<'a>(&'a self, spdx_id: &'a str, print_mode: &'a mut PrintMode) -> LocalBoxFuture<'a, Result<Option<GithubLicense>, Box<dyn Error>>> {
+        Box::pin(async move {
+            Ok(self
+                .list(print_mode)
+                .await?
+                .into_iter()
+                .find(|license| same_license(&license.spdx_id, spdx_id)))
+        })
+    }
+
+    /// Reloads all licenses, bypassing any cache.
+    ///
+    /// The default implementation is the same as `list`, for providers without a cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `print_mode` - A mutable reference to the `PrintMode` for messages.
+    fn refresh<'a>(&'a self, print_mode: &'a mut PrintMode) -> LocalBoxFuture<'a, Result<Vec<GithubLicense>, Box<dyn Error>>> {
+        self.list(print_mode)
+    }
+}
+
+/// Returns the path of the cache file for a remote provider.
+///
+/// # Arguments
+///
+/// * `cache_name` - The name of the provider, used as part of the file name.
+fn cache_file_path(cache_name: &str) -> String {
+    ProgramSettings::get_data_file_path(&format!("license-cache-{}.json", cache_name))
+}
+
+/// Returns a cache name for a remote provider, unique for every host it talks to.
+///
+/// # Arguments
+///
+/// * `prefix` - The kind of the provider, like `github` or `gitlab`.
+/// * `base_url` - The base URL of the API.
+fn cache_name_for(prefix: &str, base_url: &str) -> String {
+    let host = base_url
+        .split("://")
+        .last()
+        .unwrap_or_default()
+        .trim_end_matches('/');
+    format!(
+        "{}-{}",
+        prefix,
+        host.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>()
+    )
+}
+
+/// Reads the cached licenses of a remote provider.
+///
+/// # Arguments
+///
+/// * `cache_name` - The name of the provider, used as part of the file name.
+///
+/// # Returns
+///
+/// The cached licenses, or `None` if there is no valid, non-empty cache that is younger than `LICENSE_CACHE_MAX_AGE`.
+async fn read_cache(cache_name: &str) -> Option<Vec<GithubLicense>> {
+    let path = cache_file_path(cache_name);
+    let modified = tokio::fs::metadata(&path).await.ok()?.modified().ok()?;
+    if SystemTime::now().duration_since(modified).unwrap_or_default() > LICENSE_CACHE_MAX_AGE {
+        return None;
+    }
+    let content = tokio::fs::read_to_string(&path).await.ok()?;
+    serde_json::from_str::<Vec<GithubLicense>>(&content)
+        .ok()
+        .filter(|licenses| !licenses.is_empty())
+}
+
+/// Writes the licenses of a remote provider into its cache file.
+///
+/// # Arguments
+///
+/// * `cache_name` - The name of the provider, used as part of the file name.
+/// * `licenses` - The licenses to cache.
+/// * `print_mode` - A mutable reference to the `PrintMode` for messages.
+async fn write_cache(cache_name: &str, licenses: &[GithubLicense], print_mode: &mut PrintMode) {
+    let path = cache_file_path(cache_name);
+    match serde_json::to_string(licenses) {
+        Ok(content) => {
+            if let Err(err) = tokio::fs::write(&path, content).await {
+                print_mode.error_msg(format!("{} occurred while writing the license cache {}", err, path));
+            }
+        }
+        Err(err) => print_mode.error_msg(format!("{} occurred while serializing the license cache", err)),
+    }
+}
+
+/// Converts the response of an API for a single license into the result of `LicenseProvider::fetch`.
+///
+/// # Arguments
+///
+/// * `response` - The license, or the error of the API.
+///
+/// # Returns
+///
+/// `None` if the API does not know the license, other errors are passed on.
+fn found_license(response: Result<GithubLicense, ApiError>) -> Result<Option<GithubLicense>, Box<dyn Error>> {
+    match response {
+        Ok(license) => Ok(Some(license)),
+        Err(ApiError::NotFound { .. }) => Ok(None),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
+/// Provides the licenses of the GitHub API.
+pub struct GithubApiProvider {
+    program_settings: ProgramSettings,
+}
+
+impl GithubApiProvider {
+    pub fn new(program_settings: ProgramSettings) -> Self {
+        Self { program_settings }
+    }
+
+    /// Returns the cache name, unique for every GitHub (Enterprise) instance.
+    fn cache_name(&self) -> String {
+        cache_name_for("github", &self.program_settings.api_base_url)
+    }
+
+    /// Returns the key of a license in the GitHub API.
+    ///
+    /// The key of a cached license with the same SPDX id is used, else the key is derived from the id.
+    ///
+    /// # Arguments
+    ///
+    /// * `spdx_id` - The SPDX id of the license.
+    async fn key_of(&self, spdx_id: &str) -> String {
+        read_cache(&self.cache_name())
+            .await
+            .and_then(|cached| cached.into_iter().find(|license| same_license(&license.spdx_id, spdx_id)))
+            .map(|license| license.key)
+            .unwrap_or_else(|| github_key(spdx_id))
+    }
+}
+
+/// Derives the key of a license in the GitHub API from its SPDX id.
+///
+/// GitHub still uses the deprecated ids of the GNU licenses, so `GPL-3.0-only` has the key `gpl-3.0`.
+/// The "or later" variants have the same text, so `GPL-3.0-or-later` gets the same key.
+///
+/// # Arguments
+///
+/// * `spdx_id` - The SPDX id of the license.
+fn github_key(spdx_id: &str) -> String {
+    ["-only", "-or-later"]
+        .iter()
+        .filter_map(|suffix| spdx_id.strip_suffix(suffix))
+        .find(|deprecated| deprecated_replacement(deprecated).is_some_and(|replacement| replacement.ends_with("-only")))
+        .unwrap_or(spdx_id)
+        .to_lowercase()
+}
+
+impl LicenseProvider for GithubApiProvider {
+    fn name(&self) -> String {
+        "GitHub API".to_string()
+    }
+
+    fn list<'a>(&'a self, print_mode: &'a mut PrintMode) -> LocalBoxFuture<'a, Result<Vec<GithubLicense>, Box<dyn Error>>> {
+        Box::pin(async move {
+            if let Some(cached) = read_cache(&self.cache_name()).await {
+                print_mode.verbose_msg("Using cached licenses of the GitHub API", None);
+                return Ok(cached);
+            }
+            self.refresh(print_mode).await
+        })
+    }
+
+    fn fetch<'a>(&'a self, spdx_id: &'a str, _print_mode: &'a mut PrintMode) -> LocalBoxFuture<'a, Result<Option<GithubLicense>, Box<dyn Error>>> {
+        Box::pin(async move {
+            let found = found_license(get_license_by_key(&self.program_settings, &self.key_of(spdx_id).await).await)?;
+            // The license of an "or later" id is listed under the id of its text, like GPL-3.0.
+            Ok(found.map(|license| {
+                if same_license(&license.spdx_id, spdx_id) {
+                    license
+                } else {
+                    GithubLicense { spdx_id: spdx_id.to_string(), ..license }
+                }
+            }))
+        })
+    }
+
+    fn refresh<'a>(&'a self, print_mode: &'a mut PrintMode) -> LocalBoxFuture<'a, Result<Vec<GithubLicense>, Box<dyn Error>>> {
+        Box::pin(async move {
+            let (licenses, complete) = get_all_licenses(&self.program_settings, print_mode).await?;
+            // Partial results are not cached, so the missing licenses are fetched again next time.
+            if complete {
+                write_cache(&self.cache_name(), &licenses, print_mode).await;
+            }
+            Ok(licenses)
+        })
+    }
+}
+
+/// Provides the license templates of a GitLab instance.
+pub struct GitlabApiProvider {
+    program_settings: ProgramSettings,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl GitlabApiProvider {
+    pub fn new(program_settings: ProgramSettings, base_url: String, token: Option<String>) -> Self {
+        Self { program_settings, base_url, token }
+    }
+
+    /// Returns the cache name, unique for every GitLab instance.
+    fn cache_name(&self) -> String {
+        cache_name_for("gitlab", &self.base_url)
+    }
+}
+
+impl LicenseProvider for GitlabApiProvider {
+    fn name(&self) -> String {
+        format!("GitLab API ({})", self.base_url)
+    }
+
+    fn list<'a>(&'a self, print_mode: &'a mut PrintMode) -> LocalBoxFuture<'a, Result<Vec<GithubLicense>, Box<dyn Error>>> {
+        Box::pin(async move {
+            if let Some(cached) = read_cache(&self.cache_name()).await {
+                print_mode.verbose_msg(format!("Using cached licenses of {}", self.name()), None);
+                return Ok(cached);
+            }
+            self.refresh(print_mode).await
+        })
+    }
+
+    fn fetch<'a>(&'a self, spdx_id: &'a str, _print_mode: &'a mut PrintMode) -> LocalBoxFuture<'a, Result<Option<GithubLicense>, Box<dyn Error>>> {
+        Box::pin(async move { found_license(get_gitlab_license(&self.program_settings, &self.base_url, &self.token, &spdx_id.to_lowercase()).await) })
+    }
+
+    fn refresh<'a>(&'a self, print_mode: &'a mut PrintMode) -> LocalBoxFuture<'a, Result<Vec<GithubLicense>, Box<dyn Error>>> {
+        Box::pin(async move {
+            let licenses = get_gitlab_licenses(&self.program_settings, &self.base_url, &self.token).await?;
+            write_cache(&self.cache_name(), &licenses, print_mode).await;
+            Ok(licenses)
+        })
+    }
+}
+
+/// Provides the licenses of a SPDX license-list-data checkout or export.
+pub struct SpdxLicenseListProvider {
+    path: String,
+    only: Vec<String>,
+}
+
+impl SpdxLicenseListProvider {
+    pub fn new(path: String, only: Vec<String>) -> Self {
+        Self { path, only }
+    }
+}
+
+impl LicenseProvider for SpdxLicenseListProvider {
+    fn name(&self) -> String {
+        format!("SPDX license list ({})", self.path)
+    }
+
+    fn list<'a>(&'a self, _print_mode: &'a mut PrintMode) -> LocalBoxFuture<'a, Result<Vec<GithubLicense>, Box<dyn Error>>> {
+        Box::pin(load_spdx_licenses(&self.path, &self.only))
+    }
+
+    fn fetch<'a>(&'a self, spdx_id: &'a str, _print_mode: &'a mut PrintMode) -> LocalBoxFuture<'a, Result<Option<GithubLicense>, Box<dyn Error>>> {
+        Box::pin(async move {
+            Ok(load_spdx_licenses(&self.path, &[spdx_id.to_string()])
+                .await?
+                .into_iter()
+                .next())
+        })
+    }
+}
+
+/// Provides the licenses of a local directory with front-matter license files.
+pub struct LocalDirectoryProvider {
+    path: String,
+}
+
+impl LocalDirectoryProvider {
+    pub fn new(path: String) -> Self {
+        Self { path }
+    }
+}
+
+impl LicenseProvider for LocalDirectoryProvider {
+    fn name(&self) -> String {
+        format!("Local directory ({})", self.path)
+    }
+
+    fn list<'a>(&'a self, print_mode: &'a mut PrintMode) -> LocalBoxFuture<'a, Result<Vec<GithubLicense>, Box<dyn Error>>> {
+        Box::pin(async move { Ok(load_custom_licenses(&self.path, print_mode).await) })
+    }
+}
+
+/// Provides the license snapshot that is compiled into the binary.
+pub struct EmbeddedProvider;
+
+impl LicenseProvider for EmbeddedProvider {
+    fn name(&self) -> String {
+        "Embedded snapshot".to_string()
+    }
+
+    fn list<'a>(&'a self, _print_mode: &'a mut PrintMode) -> LocalBoxFuture<'a, Result<Vec<GithubLicense>, Box<dyn Error>>> {
+        Box::pin(async move { Ok(serde_json::from_str::<Vec<GithubLicense>>(EMBEDDED_SNAPSHOT)?) })
+    }
+}
+
+/// Appends licenses to a list, skipping every license whose SPDX id is already in it.
+///
+/// Deprecated and current ids name the same license, so the `GPL-2.0` of the GitHub API
+/// and the `GPL-2.0-only` of the SPDX list are only listed once.
+///
+/// # Arguments
+///
+/// * `licenses` - The list of licenses, modified in place.
+/// * `additional` - The licenses to append.
+fn merge_by_spdx_id(licenses: &mut Vec<GithubLicense>, additional: Vec<GithubLicense>) {
+    for license in additional {
+        if !licenses
+            .iter()
+            .any(|known| same_license(&known.spdx_id, &license.spdx_id))
+        {
+            licenses.push(license);
+        }
+    }
+}
+
+/// The ordered collection of all configured license providers.
+pub struct LicenseCatalogue {
+    providers: Vec<Box<dyn LicenseProvider>>,
+}
+
+impl LicenseCatalogue {
+    /// Creates the catalogue from the `license_sources` of the settings.
+    ///
+    /// The `custom_licenses_directory` is asked first, so custom licenses replace the licenses
+    /// of the other sources with the same SPDX id.
+    ///
+    /// # Arguments
+    ///
+    /// * `program_settings` - The program settings.
+    pub fn from_settings(program_settings: &ProgramSettings) -> Self {
+        let custom_licenses = program_settings
+            .custom_licenses_directory
+            .iter()
+            .map(|directory| LicenseSource::LocalDirectory { path: directory.clone() });
+        let mut sources: Vec<LicenseSource> = vec![];
+        for source in custom_licenses.chain(program_settings.license_sources.iter().cloned()) {
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+        Self {
+            providers: sources.iter().map(|source| source.provider(program_settings)).collect(),
+        }
+    }
+
+    /// Loads the licenses of all providers, merged and deduplicated by SPDX id.
+    ///
+    /// A failing provider is reported and skipped, as long as another provider delivered licenses.
+    ///
+    /// # Arguments
+    ///
+    /// * `refresh` - If true, every provider reloads its licenses, bypassing its cache.
+    /// * `print_mode` - A mutable reference to the `PrintMode` for messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns the last error, if no provider delivered any license.
+    pub async fn load(&self, refresh: bool, print_mode: &mut PrintMode) -> Result<Vec<GithubLicense>, Box<dyn Error>> {
+        let mut licenses: Vec<GithubLicense> = vec![];
+        let mut last_error: Option<Box<dyn Error>> = None;
+        for provider in &self.providers {
+            let result = if refresh {
+                provider.refresh(print_mode).await
+            } else {
+                provider.list(print_mode).await
+            };
+            match result {
+                Ok(provided) => {
+                    print_mode.verbose_msg(format!("Loaded {} licenses from {}", provided.len(), provider.name()), None);
+                    merge_by_spdx_id(&mut licenses, provided)
+                }
+                Err(err) => {
+                    if self.providers.len() > 1 {
+                        print_mode.error_msg(format!("{} failed to deliver licenses:\n{}", provider.name(), err));
+                    }
+                    last_error = Some(err)
+                }
+            }
+        }
+        match last_error {
+            Some(err) if licenses.is_empty() => Err(err),
+            _ => Ok(licenses),
+        }
+    }
+
+    /// Fetches a single license by its SPDX id, asking the providers in their configured order.
+    ///
+    /// # Arguments
+    ///
+    /// * `spdx_id` - The SPDX id of the license, compared case-insensitive.
+    /// * `print_mode` - A mutable reference to the `PrintMode` for messages.
+    ///
+    /// # Returns
+    ///
+    /// The license of the first provider that knows it, or `None` if no provider knows it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the last error, if no provider delivered the license and at least one of them failed.
+    pub async fn fetch(&self, spdx_id: &str, print_mode: &mut PrintMode) -> Result<Option<GithubLicense>, Box<dyn Error>> {
+        let mut last_error: Option<Box<dyn Error>> = None;
+        for provider in &self.providers {
+            match provider.fetch(spdx_id, print_mode).await {
+                Ok(Some(license)) => return Ok(Some(license)),
+                Ok(None) => {}
+                Err(err) => {
+                    if self.providers.len() > 1 {
+                        print_mode.error_msg(format!("{} could not fetch {}:\n{}", provider.name(), spdx_id, err));
+                    }
+                    last_error = Some(err)
+                }
+            }
+        }
+        match last_error {
+            Some(err) => Err(err),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A license with the given SPDX id.
+    fn license(spdx_id: &str) -> GithubLicense {
+        GithubLicense { spdx_id: spdx_id.to_string(), ..GithubLicense::default() }
+    }
+
+    #[test]
+    fn merges_deprecated_and_current_ids() {
+        let mut licenses = vec![license("GPL-2.0"), license("MIT")];
+        merge_by_spdx_id(&mut licenses, vec![license("GPL-2.0-only"), license("mit"), license("GPL-2.0-or-later"), license("0BSD")]);
+        assert_eq!(
+            licenses.iter().map(|license| license.spdx_id.as_str()).collect::<Vec<&str>>(),
+            vec!["GPL-2.0", "MIT", "GPL-2.0-or-later", "0BSD"]
+        );
+    }
+
+    #[test]
+    fn derives_the_github_key_of_gnu_licenses() {
+        assert_eq!(github_key("GPL-3.0-only"), "gpl-3.0");
+        assert_eq!(github_key("LGPL-2.1-only"), "lgpl-2.1");
+        assert_eq!(github_key("AGPL-3.0-only"), "agpl-3.0");
+        assert_eq!(github_key("Apache-2.0"), "apache-2.0");
+        assert_eq!(github_key("LGPL-2.1-or-later"), "lgpl-2.1");
+        assert_eq!(github_key("BSD-2-Clause"), "bsd-2-clause");
+    }
+}
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::license_provider::{EmbeddedProvider, GithubApiProvider, GitlabApiProvider, LicenseProvider, LocalDirectoryProvider, SpdxLicenseListProvider};
+use crate::settings_file::ProgramSettings;
+
+/// A source the available licenses are loaded from.
+///
+/// Chosen through the ordered `license_sources` list of the settings file.
+/// Licenses of earlier sources win over licenses with the same SPDX id of later ones.
+///
+/// ```json
+/// "license_sources": [
+///   { "type": "github_api" },
+///   { "type": "gitlab_api", "base_url": "https://gitlab.example.com", "token": null },
+///   { "type": "spdx_license_list", "path": "/opt/license-list-data", "only": ["EUPL-1.2", "0BSD"] },
+///   { "type": "local_directory", "path": "/opt/licenses" },
+///   { "type": "embedded" }
+/// ]
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LicenseSource {
+    /// The curated license list of the GitHub API.
+    #[default]
+    GithubApi,
+    /// The license templates of a GitLab instance.
+    GitlabApi {
+        base_url: String,
+        #[serde(default)]
+        token: Option<String>,
+    },
+    /// A local checkout or JSON export of the SPDX license-list-data.
+    SpdxLicenseList {
+        path: String,
+        #[serde(default)]
+        only: Vec<String>,
+    },
+    /// A directory of license files with front-matter, like the `custom_licenses_directory`.
+    LocalDirectory {
+        path: String,
+    },
+    /// The license snapshot that is compiled into the binary.
+    Embedded,
+}
+
+impl LicenseSource {
+    /// Creates the `LicenseProvider` for this source.
+    ///
+    /// # Arguments
+    ///
+    /// * `program_settings` - The program settings, used for the API credentials and the HTTP client.
+    ///
+    /// # Returns
+    ///
+    /// A boxed `LicenseProvider`.
+    pub fn provider(&self, program_settings: &ProgramSettings) -> Box<dyn LicenseProvider> {
+        match self {
+            LicenseSource::GithubApi => Box::new(GithubApiProvider::new(program_settings.clone())),
+            LicenseSource::GitlabApi { base_url, token } => Box::new(GitlabApiProvider::new(program_settings.clone(), base_url.clone(), token.clone())),
+            LicenseSource::SpdxLicenseList { path, only } => Box::new(SpdxLicenseListProvider::new(path.clone(), only.clone())),
+            LicenseSource::LocalDirectory { path } => Box::new(LocalDirectoryProvider::new(path.clone())),
+            LicenseSource::Embedded => Box::new(EmbeddedProvider),
+        }
+    }
+}
+
+/// Reads the `license_sources` of the settings file.
+///
+/// Settings files of older versions have a single `license_source` object instead of the list,
+/// it is read as a list with this one source.
+///
+/// # Arguments
+///
+/// * `deserializer` - The deserializer of the settings file.
+pub fn deserialize_license_sources<'de, D>(deserializer: D) -> Result<Vec<LicenseSource>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(LicenseSource),
+        Many(Vec<LicenseSource>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(source) => vec![source],
+        OneOrMany::Many(sources) => sources,
+    })
+}
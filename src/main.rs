@@ -6,23 +6,51 @@ use std::process;
 use futures::executor::block_on;
 use indicatif::{ProgressBar, ProgressStyle};
 use strum::IntoEnumIterator;
-use crate::api_communicator::get_all_licenses;
+use crate::api_error::ApiError;
 use crate::git_dir::GitDir;
 use crate::github_license::GithubLicense;
+use crate::license_provider::LicenseCatalogue;
 use crate::operating_mode::OperatingMode;
 use crate::output_printer::*;
+use crate::repo_status::{guard_repository, list_skipped, SkippedRepository, UncleanRepositoryPolicy};
+use crate::repository_index::{detection_fingerprint, RepositoryIndex};
+use crate::license_exception::LicenseException;
+use crate::license_expression::LicenseExpression;
 use crate::settings_file::ProgramSettings;
-use crate::walker::init_search;
+use crate::spdx::same_license;
+use crate::walk_filter::WalkFilter;
+use crate::walker::{init_search, load_index};
 
 // Import the other files
 mod alike;
 mod api_communicator;
+mod api_error;
+mod community_files;
+mod custom_license;
 mod error_collector;
+mod git_command;
 mod git_dir;
+mod git_metadata;
 mod github_license;
+mod license_badge;
+mod license_exception;
+mod license_expression;
+mod license_link_style;
+mod license_provider;
+mod license_source;
 mod operating_mode;
 mod output_printer;
+mod ownership;
+mod project_metadata;
+mod rate_limiter;
+mod readme_template;
+mod repo_status;
+mod repository_index;
+mod repository_kind;
 mod settings_file;
+mod spdx;
+mod spdx_license_list;
+mod walk_filter;
 mod walker;
 
 
@@ -80,7 +108,21 @@ fn print_help(pmm: &PrintMode) {
         [CONFIGURATION OPTIONS]\n\n\n\
         --initial-configuration\t\tWill ask you two questions, with one required for the program to run (username)\n\n\
         --github-user\t\tSets the github-user in the settings file\n\n\
-        --github-token\t\tSets the token for deactivating the API Limit\n\n\n\
+        --github-token\t\tSets the token for deactivating the API Limit\n\n\
+        --license <SPDX-ID>\tUses this license for every chosen repository instead of asking,\n\
+        \t\t\toptionally with an exception, like \"Apache-2.0 WITH LLVM-exception\"\n\n\
+        --refresh-licenses\tReloads the licenses of all license sources, ignoring the cached ones\n\n\
+        --rescan\t\tSearches all disks for repositories, instead of using the ones of the last search\n\n\
+        --check-license <EXPR>\tValidates a SPDX license expression, prints it normalized and exits\n\n\
+        --nested\t\tAlso searches inside of repositories, for submodules and nested repositories\n\n\
+        --include-foreign\tAlso lists repositories whose remotes belong to other users or organizations\n\n\
+        --allow-unclean\t\tWorks on repositories with uncommitted LICENSE/README changes, a merge or rebase\n\
+        \t\t\tin progress or a detached HEAD, instead of skipping them\n\n\
+        --commit\t\tCommits the files license-me changed in every repository\n\n\
+        --signoff\t\tAdds a Signed-off-by trailer to the commits\n\n\
+        --author <AUTHOR>\tOverrides the author of the commits, like \"Jane Doe <jane@example.com>\"\n\n\
+        --branch\t\tCommits the changes on a new branch like \"license-me/add-mit\" instead of the current one\n\n\
+        --patch-dir <DIR>\tCommits on a new branch and writes the commits as patches into <DIR>/<repository>\n\n\n\
         [MODE-CHANGING OPTIONS]\n\n\n\
         These options will list all git repository's with a \"LICENSE\" file in it\n\n\n\
         --append-license\tAdds a license to the chosen directory, and appends a Link to the end of README.md\n\n\
@@ -88,7 +130,9 @@ fn print_help(pmm: &PrintMode) {
         \t\t\tCreates a new one with replacing the complete \"## License\" section in your README.md\n\
         \t\t\tIt also gives you the possibility to update your current license.\n\n\
         --show-all\t\tLists all git repository's, regardless of containing a LICENSE file and aborts\n\n\
-        --unlicense\t\tDeletes a license from the chosen repositories or chosen repository"
+        --unlicense\t\tDeletes a license from the chosen repositories or chosen repository\n\n\
+        --community-files\tLists all git repository's missing CONTRIBUTING.md, CODE_OF_CONDUCT.md, SECURITY.md\n\
+        \t\t\tor (for Apache-2.0) NOTICE, creates them and links them in the README.md"
     );
     process::exit(0);
 }
@@ -251,6 +295,97 @@ fn arg_modes(arguments: Vec<String>, pmm: &mut PrintMode, settings_file: &mut Pr
                 process::exit(0)
             }
 
+            "--license" => {
+                if let Some(spdx_id) = arguments.get(count + 1) {
+                    let parsed = LicenseExpression::parse(spdx_id).and_then(|expression| expression.normalize());
+                    if let Ok(expression) = &parsed {
+                        warn_unknown_ids(expression, pmm);
+                    }
+                    match parsed {
+                        Ok(LicenseExpression::License { id, exception: None }) => settings_file.preselected_license = Some(id),
+                        Ok(LicenseExpression::License { id, exception: Some(exception) }) => {
+                            if LicenseException::find(&exception).is_none() {
+                                pmm.error_msg(format!("The text of the exception \"{}\" is not known", exception));
+                                process::exit(1)
+                            }
+                            settings_file.preselected_license = Some(id);
+                            settings_file.preselected_exception = Some(exception);
+                        }
+                        Ok(expression) => {
+                            pmm.error_msg(format!("--license takes a single license, not \"{}\"", expression));
+                            process::exit(1)
+                        }
+                        Err(error) => {
+                            pmm.error_msg(error);
+                            process::exit(1)
+                        }
+                    }
+                } else {
+                    pmm.error_msg("--license needs a SPDX id, like --license MIT");
+                    process::exit(1)
+                }
+            }
+
+            "--refresh-licenses" => settings_file.refresh_licenses = true,
+
+            // Search all disks again, instead of taking the repositories from the index
+            "--rescan" => settings_file.rescan = true,
+
+            // Also look for repositories inside of other repositories, like submodules
+            "--nested" => settings_file.find_nested_repositories = true,
+
+            // Also offer repositories whose remotes belong to other users or organizations
+            "--include-foreign" => settings_file.include_foreign = true,
+
+            // Work on repositories with uncommitted changes, merges, rebases or a detached HEAD, with a warning
+            "--allow-unclean" => settings_file.unclean_repository_policy = UncleanRepositoryPolicy::Warn,
+
+            // Commit the changed files in every repository
+            "--commit" => settings_file.commit_changes = true,
+
+            "--signoff" => settings_file.commit_signoff = true,
+
+            // Commit on a new branch, for repositories with protected main branches
+            "--branch" => settings_file.commit_on_branch = true,
+
+            "--patch-dir" => {
+                if let Some(patch_directory) = arguments.get(count + 1) {
+                    settings_file.commit_on_branch = true;
+                    settings_file.patch_directory = Some(patch_directory.clone());
+                } else {
+                    pmm.error_msg("--patch-dir needs a directory, like --patch-dir ./patches");
+                    process::exit(1)
+                }
+            }
+
+            "--author" => {
+                if let Some(author) = arguments.get(count + 1) {
+                    settings_file.commit_author = Some(author.clone());
+                } else {
+                    pmm.error_msg("--author needs a name and mail, like --author \"Jane Doe <jane@example.com>\"");
+                    process::exit(1)
+                }
+            }
+
+            // Validate and normalize a SPDX license expression, then exit
+            "--check-license" => {
+                let Some(expression) = arguments.get(count + 1) else {
+                    pmm.error_msg("--check-license needs a SPDX expression, like --check-license \"MIT OR Apache-2.0\"");
+                    process::exit(1)
+                };
+                match LicenseExpression::parse(expression).and_then(|expression| expression.normalize()) {
+                    Ok(normalized) => {
+                        warn_unknown_ids(&normalized, pmm);
+                        pmm.normal_msg(normalized.to_string());
+                        process::exit(0)
+                    }
+                    Err(error) => {
+                        pmm.error_msg(error);
+                        process::exit(1)
+                    }
+                }
+            }
+
             // Append/Add a LICENSE to a repo
             "--append-license" => op_mode = OperatingMode::AppendLicense,
 
@@ -261,6 +396,9 @@ fn arg_modes(arguments: Vec<String>, pmm: &mut PrintMode, settings_file: &mut Pr
             "--show-all" => op_mode = OperatingMode::ShowAllGitDirs,
 
             "--unlicense" => op_mode = OperatingMode::Unlicense,
+
+            // Create the missing community files, like CONTRIBUTING.md
+            "--community-files" => op_mode = OperatingMode::AddCommunityFiles,
             _ => {}
         })
     }
@@ -323,6 +461,45 @@ fn extract_and_validate_num(num_as_str: &str, len_of_vec: usize) -> Result<usize
     }
 }
 
+/// Warns about ids of a license expression, that are not on the built-in part of the SPDX list.
+///
+/// They are accepted, as they may be on the full list, but a typo (like `Apache2`) looks the same.
+///
+/// # Arguments
+///
+/// * `expression` - The normalized expression.
+/// * `pmm` - The print mode.
+fn warn_unknown_ids(expression: &LicenseExpression, pmm: &PrintMode) {
+    for id in expression.unknown_ids() {
+        pmm.normal_msg(format!(
+            "{} \"{}\" is not a SPDX id license-me knows, check it on https://spdx.org/licenses/",
+            ansi_term::Color::Yellow.bold().paint("Warning:"),
+            id
+        ));
+    }
+}
+
+/// Returns what the listing tells about the kind of a repository, like ` (submodule of /home/me/app, 2 worktrees)`.
+///
+/// # Arguments
+///
+/// * `dir` - The repository.
+fn repository_notes(dir: &GitDir) -> String {
+    let mut notes: Vec<String> = vec![];
+    let kind = dir.kind.to_string();
+    if !kind.is_empty() {
+        notes.push(kind);
+    }
+    if !dir.worktrees.is_empty() {
+        notes.push(format!("{} worktrees", dir.worktrees.len() + 1));
+    }
+    if notes.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", notes.join(", "))
+    }
+}
+
 /// Presents the directories and their information based on the operating mode and print mode.
 ///
 /// # Arguments
@@ -330,34 +507,94 @@ fn extract_and_validate_num(num_as_str: &str, len_of_vec: usize) -> Result<usize
 /// * `directories` - A vector of `GitDir` structs representing the directories.
 /// * `operating_mode` - An `OperatingMode` enum representing the mode of operation.
 /// * `print_mode` - A `PrintMode` enum representing the mode of printing.
+/// * `settings` - The `ProgramSettings`, that decide which repositories are owned.
 ///
 /// # Returns
 ///
 /// * A `Result` containing a vector of `usize` representing the selected directories, or a boxed dynamic `Error` trait object.
-fn present_dirs(directories: &Vec<GitDir>, operating_mode: &OperatingMode, print_mode: &PrintMode) -> Result<Vec<usize>, Box<dyn Error>> {
+fn present_dirs(directories: &[GitDir], operating_mode: &OperatingMode, print_mode: &PrintMode, settings: &ProgramSettings) -> Result<Vec<usize>, Box<dyn Error>> {
+    // Only owned and local repositories are offered for modifications, unless --include-foreign is given.
+    let owners = settings.owners();
+    // Bare repositories have no working tree to edit.
+    let is_offered =
+        |dir: &GitDir| dir.kind.has_work_tree() && dir.ownership(&owners).is_offered(settings.include_foreign);
+    let mut hidden_foreign_dirs: usize = 0;
+
+    // The branch and remote columns are as wide as their longest value.
+    let column_width = |column: usize, header: &str| {
+        directories
+            .iter()
+            .map(|dir| dir.metadata.columns()[column].len())
+            .chain([header.len()])
+            .max()
+            .unwrap_or_default()
+    };
+    let (branch_width, remote_width) = (column_width(0, "Branch"), column_width(3, "Remote"));
+    if operating_mode == &OperatingMode::ShowAllGitDirs {
+        println!(
+            "{:<branch_width$}  {:<11}  {:>7}  {:<10}  {:<remote_width$}  Repository",
+            "Branch", "Last commit", "Commits", "Ownership", "Remote"
+        );
+    }
     directories.iter().enumerate().for_each(|(count, dir)| {
+        if operating_mode != &OperatingMode::ShowAllGitDirs && !is_offered(dir) {
+            if dir.kind.has_work_tree() {
+                hidden_foreign_dirs += 1;
+            }
+            return;
+        }
         match operating_mode {
             OperatingMode::SetNewLicense => {
                 if dir.license_path.is_none() || dir.license.is_none() {
-                    println!("[{}] {}", count + 1, dir.path);
+                    println!("[{}] {}{}", count + 1, dir.path, repository_notes(dir));
+                }
+            }
+            OperatingMode::AddCommunityFiles => {
+                let missing = dir.missing_community_files();
+                if !missing.is_empty() {
+                    println!(
+                        "[{}] {} (missing: {})",
+                        count + 1,
+                        dir.path,
+                        missing.iter().map(|file| file.file_name()).collect::<Vec<&str>>().join(", ")
+                    );
                 }
             }
             OperatingMode::ShowAllGitDirs => {
+                let [branch, last_commit, commits, remote] = dir.metadata.columns();
                 println!(
-                    "[License: {}][Readme: {}] {}",
+                    "{:<branch_width$}  {:<11}  {:>7}  {:<10}  {:<remote_width$}  [License: {}][Readme: {}] {}{}{}",
+                    branch,
+                    last_commit,
+                    commits,
+                    dir.ownership(&owners).to_string(),
+                    remote,
                     PrintMode::colored_bools(&(dir.license_path.is_some() || dir.license.is_some())),
                     PrintMode::colored_bools(&dir.readme_path.is_some()),
-                    dir.path
+                    dir.path,
+                    dir.metadata
+                        .fork_of
+                        .as_ref()
+                        .map(|upstream| format!(" (fork of {})", upstream))
+                        .unwrap_or_default(),
+                    repository_notes(dir)
                 );
             }
             _ => {
                 if dir.license_path.is_some() || dir.license.is_some() {
-                    println!("[{}] {}", count + 1, dir.path);
+                    println!("[{}] {}{}", count + 1, dir.path, repository_notes(dir));
                 }
             }
         }
     });
 
+    if hidden_foreign_dirs > 0 {
+        print_mode.normal_msg(format!(
+            "\n{} repositories of other owners are hidden, use --include-foreign to list them",
+            hidden_foreign_dirs
+        ));
+    }
+
     // If the user just wanted to see how many git directories he has....
     if operating_mode == &OperatingMode::ShowAllGitDirs {
         print_mode.normal_msg("\n\nPlease run again for modifying the directories\n");
@@ -401,10 +638,17 @@ fn present_dirs(directories: &Vec<GitDir>, operating_mode: &OperatingMode, print
             });
             range.for_each(|choice| input_of_user.push(choice))
         }
-        x if x.contains("all") => { directories.iter().enumerate().for_each(|entry| input_of_user.push(entry.0)) }
+        x if x.contains("all") => { directories.iter().enumerate().for_each(|entry| input_of_user.push(entry.0 + 1)) }
         x if x.parse::<usize>().is_ok() => { input_of_user.push(extract_and_validate_num(x, directories.len())?) }
         _ => {}
     }
+    // Hidden repositories cannot be chosen by their number either.
+    input_of_user.retain(|choice| {
+        choice
+            .checked_sub(1)
+            .and_then(|index| directories.get(index))
+            .is_none_or(is_offered)
+    });
     Ok(input_of_user)
 }
 
@@ -448,15 +692,21 @@ fn present_dirs(directories: &Vec<GitDir>, operating_mode: &OperatingMode, print
 ///     },
 /// }
 /// ```
-async fn recursive_main(found_git_dirs: &mut Vec<GitDir>, all_licenses: Vec<GithubLicense>, mut print_mode: PrintMode, settings: ProgramSettings, operating_mode: OperatingMode) -> Result<usize, Box<dyn Error>> {
+async fn recursive_main(found_git_dirs: &mut [GitDir], all_licenses: Vec<GithubLicense>, mut print_mode: PrintMode, settings: ProgramSettings, operating_mode: OperatingMode, skipped_dirs: &mut Vec<SkippedRepository>) -> Result<usize, Box<dyn Error>> {
     let mut processed_dirs_count: usize = 0;
-    let chosen_dirs = present_dirs(found_git_dirs, &operating_mode, &print_mode)?;
+    let chosen_dirs = present_dirs(found_git_dirs, &operating_mode, &print_mode, &settings)?;
 
     for chosen_nums in &chosen_dirs {
         let chosen_dir = &mut found_git_dirs[chosen_nums - 1];
         clear_term();
+        if let Some(skipped) = guard_repository(&chosen_dir.path, settings.unclean_repository_policy, &print_mode).await {
+            skipped_dirs.push(skipped);
+            continue;
+        }
         if operating_mode == OperatingMode::Unlicense {
             print_mode.normal_msg(format!("Deleting license from {} ...", chosen_dir.project_title))
+        } else if operating_mode == OperatingMode::AddCommunityFiles {
+            print_mode.normal_msg(format!("Adding community files to {} ...", chosen_dir.project_title))
         } else {
             print_mode.normal_msg(format!(
                 "Directory {} of {}",
@@ -474,10 +724,19 @@ async fn recursive_main(found_git_dirs: &mut Vec<GitDir>, all_licenses: Vec<Gith
             ));
             if let Some(license) = &chosen_dir.license {
                 print_mode.normal_msg(format!("Recognized the \"{}\" License!", license.name));
+                if let Some(expression) = chosen_dir.license_expression.as_ref().filter(|e| e.license_ids().len() > 1) {
+                    print_mode.normal_msg(format!("The repository is licensed under \"{}\"", expression));
+                }
+                if let Some(LicenseExpression::License { exception: Some(exception), .. }) = &chosen_dir.license_expression {
+                    if let Some(known) = LicenseException::find(exception) {
+                        print_mode.normal_msg(format!("Recognized the \"{}\"!", known.name));
+                    }
+                }
             } else {
                 print_mode.normal_msg("\n\n");
             }
         }
+        let previous_expression = chosen_dir.license_expression.clone();
         chosen_dir
             .execute_user_action(
                 &settings,
@@ -486,6 +745,9 @@ async fn recursive_main(found_git_dirs: &mut Vec<GitDir>, all_licenses: Vec<Gith
                 all_licenses.clone(),
             )
             .await;
+        chosen_dir
+            .commit_changes(&settings, &mut print_mode, &operating_mode, previous_expression)
+            .await;
         processed_dirs_count += 1;
     }
     Ok(processed_dirs_count)
@@ -511,14 +773,11 @@ fn print_initial() {
 /// 4. Initializes the `ProgramSettings` struct by calling the `init` method.
 /// 5. Initializes variables to keep track of the processed directories count, licenses, and found git directories.
 /// 6. Enters a loop that continues until there are no remaining licenses or git directories.
-/// 7. If both the licenses and git directories are empty, it calls the `get_all_licenses` function to populate the `all_licenses` vector and the `init
+/// 7. If both the licenses and git directories are empty, it loads the licenses from the `LicenseCatalogue` to populate the `all_licenses` vector and the `init
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     print_initial();
 
-    // Starting time measurement
-    let sys_time: tokio::time::Instant = tokio::time::Instant::now();
-
     // Init the Print mode Struct
     let mut print_mode: PrintMode = PrintMode::norm();
 
@@ -530,18 +789,58 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // Check the given arguments
     let mut operating_mode: OperatingMode = arg_modes(args().collect::<Vec<String>>(), &mut print_mode, &mut settings);
 
+    let license_catalogue = LicenseCatalogue::from_settings(&settings);
     let mut all_licenses: Vec<GithubLicense> = vec![];
     let mut found_git_dirs: Vec<GitDir> = vec![];
+    let mut skipped_dirs: Vec<SkippedRepository> = vec![];
 
     loop {
         if all_licenses.is_empty() && found_git_dirs.is_empty() {
             let progress_bar: ProgressBar = progress_spinner();
-            all_licenses = get_all_licenses(&settings).await?;
-            found_git_dirs = init_search(sys_time, all_licenses.clone()).await;
+            all_licenses = match license_catalogue.load(settings.refresh_licenses, &mut print_mode).await {
+                Ok(licenses) => licenses,
+                Err(err) => {
+                    progress_bar.finish_and_clear();
+                    // API errors carry their kind and exit code, so scripts can react on them.
+                    if let Some(api_error) = err.downcast_ref::<ApiError>() {
+                        print_mode.error_msg(format!("[{}] {}", api_error.kind(), api_error));
+                        process::exit(api_error.exit_code())
+                    }
+                    return Err(err);
+                }
+            };
+            if let Some(spdx_id) = &settings.preselected_license {
+                if !all_licenses.iter().any(|license| same_license(&license.spdx_id, spdx_id)) {
+                    match license_catalogue.fetch(spdx_id, &mut print_mode).await {
+                        Ok(Some(license)) => all_licenses.push(license),
+                        Ok(None) => {
+                            progress_bar.finish_and_clear();
+                            print_mode.error_msg(format!("None of the license sources knows the license \"{}\"", spdx_id));
+                            process::exit(1)
+                        }
+                        Err(err) => {
+                            progress_bar.finish_and_clear();
+                            print_mode.error_msg(format!("The license \"{}\" could not be fetched:\n{}", spdx_id, err));
+                            process::exit(err.downcast_ref::<ApiError>().map_or(1, ApiError::exit_code))
+                        }
+                    }
+                }
+            }
+            // The index of the last complete search is only used with the same filter, else all disks are searched.
+            let filter = WalkFilter::from_settings(&settings);
+            // Repositories analysed with other licenses are analysed again.
+            let fingerprint = detection_fingerprint(&all_licenses, &settings);
+            let index = if settings.rescan { None } else { RepositoryIndex::read(&filter).await };
+            let (git_dirs, summary) = match index {
+                Some(index) => load_index(index, fingerprint, all_licenses.clone(), &mut print_mode).await,
+                None => init_search(all_licenses.clone(), filter, fingerprint, &progress_bar, &mut print_mode).await,
+            };
             progress_bar.finish_and_clear();
+            print_mode.normal_msg(summary);
+            found_git_dirs = git_dirs;
         }
 
-        if let Ok(num) = recursive_main(&mut found_git_dirs, all_licenses.clone(), print_mode.clone(), settings.clone(), operating_mode).await {
+        if let Ok(num) = recursive_main(&mut found_git_dirs, all_licenses.clone(), print_mode.clone(), settings.clone(), operating_mode, &mut skipped_dirs).await {
             processed_dirs_count += num;
             if ask_a_question("Do you want to repeat the Process?") {
                 OperatingMode::iter().enumerate().for_each(|(c, n)| {
@@ -564,6 +863,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
         }
     }
 
+    // List the repositories that were not touched, because of their state.
+    list_skipped(&skipped_dirs, &print_mode);
+
     // Print all errors the program collected at last.
     print_mode
         .err_col
@@ -11,6 +11,7 @@ pub enum OperatingMode {
     LicenseReplace,
     ShowAllGitDirs,
     Unlicense, //Deletes the license file and removes links in the readme file.
+    AddCommunityFiles, //Creates missing CONTRIBUTING, CODE_OF_CONDUCT, SECURITY and NOTICE files.
 }
 
 impl OperatingMode {
@@ -28,6 +29,7 @@ impl OperatingMode {
     /// If `i` is 3, returns `Some(Self::LicenseReplace)`.
     /// If `i` is 4, returns `Some(Self::ShowAllGitDirs)`.
     /// If `i` is 5, returns `Some(Self::Unlicense)`.
+    /// If `i` is 6, returns `Some(Self::AddCommunityFiles)`.
     /// Otherwise, returns `None`.
     pub fn from_usize(i: usize) -> Option<Self> {
         match i {
@@ -36,7 +38,23 @@ impl OperatingMode {
             3 => { Some(Self::LicenseReplace) }
             4 => { Some(Self::ShowAllGitDirs) }
             5 => { Some(Self::Unlicense) }
+            6 => { Some(Self::AddCommunityFiles) }
             _ => None
         }
     }
+
+    /// Returns the `{action}` of the commit message template for the license changes of this mode.
+    ///
+    /// # Returns
+    ///
+    /// The verb, or `None` if the mode does not change the license.
+    pub fn commit_action(&self) -> Option<&'static str> {
+        match self {
+            Self::SetNewLicense => Some("add"),
+            Self::AppendLicense => Some("append"),
+            Self::LicenseReplace => Some("replace"),
+            Self::Unlicense => Some("remove"),
+            Self::ShowAllGitDirs | Self::AddCommunityFiles => None,
+        }
+    }
 }
@@ -107,7 +107,7 @@ impl PrintMode {
     ///
     /// * `msg` - The message to be printed. Must implement `std::fmt::Display`.
     /// * `bar_opt` - An optional `ProgressBar` instance. If provided, the progress bar will be suspended while
-    ///               printing the message.
+    ///   printing the message.
     ///
     /// # Example
     ///
@@ -0,0 +1,74 @@
+use std::fmt::{Display, Formatter};
+
+use crate::git_metadata::RepositoryMetadata;
+
+// The walker also finds cloned third-party projects, which must never be relicensed.
+// The remotes of a repository tell whether it belongs to one of the owned users or organizations.
+
+/// Who a repository belongs to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Ownership {
+    /// A remote points at an owned user or organization.
+    Owned,
+    /// All remotes point at other users or organizations.
+    Foreign,
+    /// The repository has no remote on a host.
+    LocalOnly,
+}
+
+impl Ownership {
+    /// Classifies a repository by its remotes.
+    ///
+    /// # Arguments
+    ///
+    /// * `metadata` - The metadata of the repository, with its remotes.
+    /// * `owners` - The owned users and organizations, like `octocat` or `my-org/subgroup`.
+    pub fn of(metadata: &RepositoryMetadata, owners: &[String]) -> Self {
+        let slugs = metadata
+            .remotes
+            .iter()
+            .filter_map(|remote| remote.slug.as_deref())
+            .collect::<Vec<&str>>();
+        if slugs.is_empty() {
+            Self::LocalOnly
+        } else if slugs.iter().any(|slug| owners.iter().any(|owner| is_owned_by(slug, owner))) {
+            Self::Owned
+        } else {
+            Self::Foreign
+        }
+    }
+
+    /// Checks whether repositories of this kind are offered in the modifying modes.
+    ///
+    /// # Arguments
+    ///
+    /// * `include_foreign` - Whether `--include-foreign` was given.
+    pub fn is_offered(&self, include_foreign: bool) -> bool {
+        include_foreign || self != &Self::Foreign
+    }
+}
+
+impl Display for Ownership {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Owned => write!(f, "owned"),
+            Self::Foreign => write!(f, "foreign"),
+            Self::LocalOnly => write!(f, "local only"),
+        }
+    }
+}
+
+/// Checks whether the namespace of a repository path belongs to an owner.
+///
+/// # Arguments
+///
+/// * `slug` - The path of the repository, like `octocat/hello` or `group/subgroup/project`.
+/// * `owner` - The user or organization, a group also owns its subgroups.
+fn is_owned_by(slug: &str, owner: &str) -> bool {
+    let slug = slug.to_lowercase();
+    let Some((namespace, _)) = slug.rsplit_once('/') else {
+        return false;
+    };
+    let owner = owner.trim_matches('/').to_lowercase();
+    !owner.is_empty() && (namespace == owner || namespace.starts_with(&format!("{}/", owner)))
+}
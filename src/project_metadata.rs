@@ -0,0 +1,625 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+// Infers metadata of a project (description, language, build and test commands) from its
+// manifest files, so a generated README has more than boilerplate in it.
+
+/// Files that mark the main language of a project, checked in this order.
+static LANGUAGE_MARKERS: [(&str, &str); 14] = [
+    ("Cargo.toml", "Rust"),
+    ("go.mod", "Go"),
+    ("tsconfig.json", "TypeScript"),
+    ("package.json", "JavaScript"),
+    ("pyproject.toml", "Python"),
+    ("setup.py", "Python"),
+    ("requirements.txt", "Python"),
+    ("pom.xml", "Java"),
+    ("build.gradle", "Java"),
+    ("build.gradle.kts", "Kotlin"),
+    ("Gemfile", "Ruby"),
+    ("composer.json", "PHP"),
+    ("Package.swift", "Swift"),
+    ("CMakeLists.txt", "C++"),
+];
+
+/// File extensions counted for the language detection, if no marker file exists.
+static LANGUAGE_EXTENSIONS: [(&str, &str); 22] = [
+    ("rs", "Rust"),
+    ("go", "Go"),
+    ("ts", "TypeScript"),
+    ("tsx", "TypeScript"),
+    ("js", "JavaScript"),
+    ("jsx", "JavaScript"),
+    ("py", "Python"),
+    ("java", "Java"),
+    ("kt", "Kotlin"),
+    ("rb", "Ruby"),
+    ("php", "PHP"),
+    ("swift", "Swift"),
+    ("c", "C"),
+    ("h", "C"),
+    ("cpp", "C++"),
+    ("cc", "C++"),
+    ("hpp", "C++"),
+    ("cs", "C#"),
+    ("sh", "Shell"),
+    ("lua", "Lua"),
+    ("dart", "Dart"),
+    ("zig", "Zig"),
+];
+
+/// Directories skipped while counting file extensions, they hold dependencies or build output.
+static IGNORED_DIRECTORIES: [&str; 8] = [
+    ".git",
+    "target",
+    "node_modules",
+    "vendor",
+    "build",
+    "dist",
+    "venv",
+    "__pycache__",
+];
+
+/// How deep the extension counting walks into the project.
+static MAX_SCAN_DEPTH: usize = 6;
+
+/// Makefile targets that build the project, the first one found is used.
+static MAKE_BUILD_TARGETS: [&str; 2] = ["build", "all"];
+/// Makefile targets that run the tests, the first one found is used.
+static MAKE_TEST_TARGETS: [&str; 2] = ["test", "check"];
+
+/// The metadata inferred from the files of a project.
+#[derive(Debug, Clone, Default)]
+pub struct ProjectMetadata {
+    /// The description from a manifest file, like the `description` of `Cargo.toml`.
+    pub(crate) description: Option<String>,
+    /// The main language of the project.
+    pub(crate) language: Option<String>,
+    /// The commands that build the project.
+    pub(crate) build_commands: Vec<String>,
+    /// The commands that run the tests of the project.
+    pub(crate) test_commands: Vec<String>,
+}
+
+impl ProjectMetadata {
+    /// Infers the metadata of a project.
+    ///
+    /// The manifests are read in this order: `Cargo.toml`, `package.json`, `pyproject.toml`,
+    /// `Makefile`. The first description wins, the commands of all manifests are collected.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the project.
+    ///
+    /// # Returns
+    ///
+    /// The inferred metadata, missing values are left empty.
+    pub async fn infer(path: &str) -> Self {
+        let root = Path::new(path);
+        let mut metadata = Self {
+            language: detect_language(path),
+            ..Self::default()
+        };
+
+        if let Ok(content) = tokio::fs::read_to_string(root.join("Cargo.toml")).await {
+            metadata.add_description(toml_value(&content, "package", "description"));
+            metadata.build_commands.push("cargo build --release".to_string());
+            metadata.test_commands.push("cargo test".to_string());
+        }
+        if let Ok(content) = tokio::fs::read_to_string(root.join("package.json")).await {
+            metadata.add_package_json(&content);
+        }
+        if let Ok(content) = tokio::fs::read_to_string(root.join("pyproject.toml")).await {
+            metadata.add_description(
+                toml_value(&content, "project", "description")
+                    .or_else(|| toml_value(&content, "tool.poetry", "description")),
+            );
+            metadata.build_commands.push("pip install .".to_string());
+            if content.contains("pytest") || root.join("tests").is_dir() {
+                metadata.test_commands.push("pytest".to_string());
+            }
+        }
+        if let Ok(content) = tokio::fs::read_to_string(root.join("Makefile")).await {
+            let targets = makefile_targets(&content);
+            if let Some(target) = MAKE_BUILD_TARGETS.iter().find(|t| targets.iter().any(|x| x == *t)) {
+                metadata.build_commands.push(format!("make {}", target));
+            }
+            if let Some(target) = MAKE_TEST_TARGETS.iter().find(|t| targets.iter().any(|x| x == *t)) {
+                metadata.test_commands.push(format!("make {}", target));
+            }
+        }
+        if metadata.build_commands.is_empty() && root.join("go.mod").exists() {
+            metadata.build_commands.push("go build ./...".to_string());
+            metadata.test_commands.push("go test ./...".to_string());
+        }
+        metadata
+    }
+
+    /// Sets the description, unless there is one already or the new one is empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `description` - The description found in a manifest.
+    fn add_description(&mut self, description: Option<String>) {
+        if self.description.is_none() {
+            self.description = description.filter(|d| !d.trim().is_empty());
+        }
+    }
+
+    /// Reads the description and the `build` and `test` scripts of a `package.json`.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The content of the `package.json`.
+    fn add_package_json(&mut self, content: &str) {
+        let Ok(package) = serde_json::from_str::<serde_json::Value>(content) else {
+            return;
+        };
+        self.add_description(package["description"].as_str().map(str::to_string));
+        self.build_commands.push("npm install".to_string());
+        if let Some(scripts) = package["scripts"].as_object() {
+            if scripts.contains_key("build") {
+                self.build_commands.push("npm run build".to_string());
+            }
+            if scripts.contains_key("test") {
+                self.test_commands.push("npm test".to_string());
+            }
+        }
+    }
+}
+
+/// Reads the license expression a project declares in its manifest.
+///
+/// # Arguments
+///
+/// * `path` - The path of the project.
+///
+/// # Returns
+///
+/// The `license` of `Cargo.toml` or `package.json`, like `MIT OR Apache-2.0`, if there is one.
+pub async fn manifest_license(path: &str) -> Option<String> {
+    let root = Path::new(path);
+    if let Ok(content) = tokio::fs::read_to_string(root.join("Cargo.toml")).await {
+        if let Some(license) = toml_value(&content, "package", "license") {
+            return Some(license);
+        }
+    }
+    let content = tokio::fs::read_to_string(root.join("package.json")).await.ok()?;
+    let package = serde_json::from_str::<serde_json::Value>(&content).ok()?;
+    package["license"].as_str().map(str::to_string)
+}
+
+/// Detects the main language of a project.
+///
+/// Marker files in the root directory (like `Cargo.toml`) win; without one the most common
+/// source file extension decides.
+///
+/// # Arguments
+///
+/// * `path` - The path of the project.
+///
+/// # Returns
+///
+/// The name of the language, or `None` if it could not be detected.
+pub fn detect_language(path: &str) -> Option<String> {
+    LANGUAGE_MARKERS
+        .iter()
+        .find(|(marker, _)| Path::new(path).join(marker).exists())
+        .map(|(_, language)| language.to_string())
+        .or_else(|| language_by_extensions(path))
+}
+
+/// Counts the source files per language and returns the language with the most files.
+///
+/// # Arguments
+///
+/// * `path` - The path of the project.
+fn language_by_extensions(path: &str) -> Option<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    WalkDir::new(path)
+        .max_depth(MAX_SCAN_DEPTH)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || !IGNORED_DIRECTORIES.contains(&entry.file_name().to_string_lossy().as_ref())
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .for_each(|entry| {
+            let extension = entry
+                .path()
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if let Some((_, language)) = LANGUAGE_EXTENSIONS.iter().find(|(e, _)| *e == extension) {
+                *counts.entry(language).or_default() += 1;
+            }
+        });
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(a.0)))
+        .map(|(language, _)| language.to_string())
+}
+
+/// Returns the file name friendly key of a language, like `rust`, `csharp` or `cpp`.
+///
+/// # Arguments
+///
+/// * `language` - The name of the language.
+pub fn language_key(language: &str) -> String {
+    language
+        .to_lowercase()
+        .replace('#', "sharp")
+        .replace("++", "pp")
+        .replace(' ', "-")
+}
+
+/// Reads a string value of a TOML table, enough for the flat keys of manifests.
+///
+/// # Arguments
+///
+/// * `content` - The content of the TOML file.
+/// * `table` - The name of the table, like `package` or `tool.poetry`.
+/// * `key` - The key inside of the table.
+///
+/// # Returns
+///
+/// The value without quotes, or `None` if the key is not in the table.
+fn toml_value(content: &str, table: &str, key: &str) -> Option<String> {
+    let mut in_table = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_table = line.trim_matches(|c| c == '[' || c == ']').trim() == table;
+            continue;
+        }
+        if !in_table {
+            continue;
+        }
+        if let Some((name, value)) = line.split_once('=') {
+            if name.trim() == key {
+                let value = value.trim();
+                let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+                let value = &value[1..];
+                return value.find(quote).map(|end| value[..end].to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Returns the targets of a Makefile, without special targets like `.PHONY`.
+///
+/// # Arguments
+///
+/// * `content` - The content of the Makefile.
+fn makefile_targets(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter(|line| !line.starts_with(['\t', ' ', '#', '.']))
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(_, rest)| !rest.starts_with('='))
+        .flat_map(|(targets, _)| targets.split_whitespace().map(str::to_string).collect::<Vec<String>>())
+        .collect()
+}
+
+/// Writes the license expression into the manifests of a project, so they match the license files.
+///
+/// `Cargo.toml` gets the `license` key of `[package]` (unless it uses `license-file`, or inherits the license
+/// of its workspace), `package.json` gets its top-level `license` field. Missing manifests are skipped.
+///
+/// # Arguments
+///
+/// * `path` - The path of the project.
+/// * `expression` - The SPDX license expression, like `MIT OR Apache-2.0`.
+///
+/// # Returns
+///
+/// The paths of the updated manifests, and why other manifests were not updated.
+///
+/// # Errors
+///
+/// Returns an error if a manifest cannot be written.
+pub async fn write_manifest_license(
+    path: &str,
+    expression: &str,
+) -> Result<(Vec<PathBuf>, Vec<String>), Box<dyn Error>> {
+    let root = Path::new(path);
+    let mut updated: Vec<PathBuf> = vec![];
+    let mut not_updated: Vec<String> = vec![];
+
+    let cargo_toml = root.join("Cargo.toml");
+    if let Ok(content) = tokio::fs::read_to_string(&cargo_toml).await {
+        if let Some(new_content) = set_cargo_license(&content, expression) {
+            tokio::fs::write(&cargo_toml, new_content).await?;
+            updated.push(cargo_toml);
+        }
+    }
+
+    let package_json = root.join("package.json");
+    if let Ok(content) = tokio::fs::read_to_string(&package_json).await {
+        match set_package_json_license(&content, expression) {
+            Ok(Some(new_content)) => {
+                tokio::fs::write(&package_json, new_content).await?;
+                updated.push(package_json);
+            }
+            Ok(None) => {}
+            Err(reason) => not_updated.push(format!("{} was not updated, {}", package_json.display(), reason)),
+        }
+    }
+    Ok((updated, not_updated))
+}
+
+/// Sets the `license` of the `[package]` table of a `Cargo.toml`, after the `version` if it is new.
+///
+/// # Arguments
+///
+/// * `content` - The content of the `Cargo.toml`.
+/// * `expression` - The SPDX license expression.
+///
+/// # Returns
+///
+/// The new content, or `None` if there is no `[package]` table, the license is already set,
+/// the package uses a `license-file` or inherits the license of its workspace (`license.workspace = true`).
+fn set_cargo_license(content: &str, expression: &str) -> Option<String> {
+    if toml_value(content, "package", "license").as_deref() == Some(expression)
+        || content.lines().any(|line| line.trim() == "[package.license]")
+    {
+        return None;
+    }
+    let license_line = format!("license = \"{}\"", expression);
+    let mut lines = content.lines().map(str::to_string).collect::<Vec<String>>();
+    let package = lines.iter().position(|line| line.trim() == "[package]")?;
+    let table_end = lines[package + 1..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .map(|end| package + 1 + end)
+        .unwrap_or(lines.len());
+    let key_of = |line: &String| line.split_once('=').map(|(key, _)| key.trim().to_string());
+    // Dotted keys like `license.workspace` and inline tables like `license = { workspace = true }` are inherited.
+    let license_keys = (package + 1..table_end)
+        .filter(|i| {
+            key_of(&lines[*i]).is_some_and(|key| {
+                let name = key.split('.').next().unwrap_or_default().trim().trim_matches('"');
+                name == "license" || name == "license-file"
+            })
+        })
+        .collect::<Vec<usize>>();
+    let is_plain_license = |i: &usize| {
+        key_of(&lines[*i]).as_deref() == Some("license")
+            && !lines[*i].split_once('=').is_some_and(|(_, value)| value.trim_start().starts_with('{'))
+    };
+    if !license_keys.iter().all(is_plain_license) {
+        return None;
+    }
+    if let Some(existing) = license_keys.first() {
+        lines[*existing] = license_line;
+    } else {
+        let after = (package + 1..table_end)
+            .find(|i| key_of(&lines[*i]).as_deref() == Some("version"))
+            .unwrap_or(package);
+        lines.insert(after + 1, license_line);
+    }
+    Some(lines.join("\n") + if content.ends_with('\n') { "\n" } else { "" })
+}
+
+/// Sets the top-level `license` field of a `package.json`, after the `version` field if it is new.
+///
+/// The file is edited as text, so the order and formatting of the other fields is kept. Fields of nested
+/// objects (like the `license` of `publishConfig` or `overrides`) are left alone.
+///
+/// # Arguments
+///
+/// * `content` - The content of the `package.json`.
+/// * `expression` - The SPDX license expression.
+///
+/// # Returns
+///
+/// The new content, or `None` if the license is already set.
+///
+/// # Errors
+///
+/// Returns the reason, if the `package.json` is no JSON object.
+fn set_package_json_license(content: &str, expression: &str) -> Result<Option<String>, String> {
+    let package = serde_json::from_str::<serde_json::Value>(content).map_err(|error| format!("it is no valid JSON ({})", error))?;
+    if !package.is_object() {
+        return Err("it is no JSON object".to_string());
+    }
+    if package["license"].as_str() == Some(expression) {
+        return Ok(None);
+    }
+    let license = serde_json::to_string(expression).map_err(|error| error.to_string())?;
+    let fields = top_level_fields(content).ok_or_else(|| "its fields could not be read".to_string())?;
+
+    let new_content = if let Some((_, _, value)) = fields.iter().find(|(name, _, _)| name == "license") {
+        format!("{}{}{}", &content[..value.start], license, &content[value.end..])
+    } else if let Some((_, key, value)) = fields
+        .iter()
+        .find(|(name, _, _)| name == "version")
+        .or_else(|| fields.last())
+    {
+        // The new field is formatted like the one it follows, on its own line or on the same one.
+        let indent = &content[content[..key.start].trim_end().len()..key.start];
+        let separator = &content[key.end..value.start];
+        format!(
+            "{},{}\"license\"{}{}{}",
+            &content[..value.end],
+            indent,
+            separator,
+            license,
+            &content[value.end..]
+        )
+    } else {
+        let object_start = content.find('{').ok_or_else(|| "it is no JSON object".to_string())? + 1;
+        format!("{}\"license\": {}{}", &content[..object_start], license, &content[object_start..])
+    };
+    Ok(Some(new_content))
+}
+
+/// A field of a JSON object: its name, the byte range of its key (with the quotes) and of its value.
+type JsonField = (String, Range<usize>, Range<usize>);
+
+/// Returns the fields of the top-level object of a JSON document.
+///
+/// # Arguments
+///
+/// * `content` - The JSON document, it has to be valid.
+///
+/// # Returns
+///
+/// The fields in their order, or `None` if the document is no object.
+fn top_level_fields(content: &str) -> Option<Vec<JsonField>> {
+    let bytes = content.as_bytes();
+    let skip_whitespace = |mut index: usize| {
+        while index < bytes.len() && bytes[index].is_ascii_whitespace() {
+            index += 1;
+        }
+        index
+    };
+    let mut index = skip_whitespace(content.strip_prefix('\u{feff}').map_or(0, |_| '\u{feff}'.len_utf8()));
+    if bytes.get(index) != Some(&b'{') {
+        return None;
+    }
+    index += 1;
+
+    let mut fields: Vec<JsonField> = vec![];
+    loop {
+        index = skip_whitespace(index);
+        match bytes.get(index)? {
+            b'}' => return Some(fields),
+            b',' => {
+                index += 1;
+                continue;
+            }
+            _ => {}
+        }
+        let key_end = json_value_end(bytes, index)?;
+        let name = serde_json::from_str::<String>(&content[index..key_end]).ok()?;
+        let value_start = skip_whitespace(skip_whitespace(key_end) + 1);
+        let value_end = json_value_end(bytes, value_start)?;
+        fields.push((name, index..key_end, value_start..value_end));
+        index = value_end;
+    }
+}
+
+/// Returns where a JSON value ends: after its closing quote or bracket, or before the comma or bracket after it.
+///
+/// # Arguments
+///
+/// * `bytes` - The JSON document.
+/// * `start` - Where the value starts.
+fn json_value_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut index = start;
+    while index < bytes.len() {
+        let byte = bytes[index];
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => {
+                    in_string = false;
+                    if depth == 0 {
+                        return Some(index + 1);
+                    }
+                }
+                _ => {}
+            }
+        } else {
+            match byte {
+                b'"' => in_string = true,
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' if depth > 0 => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(index + 1);
+                    }
+                }
+                b',' | b'}' | b']' => {
+                    let mut end = index;
+                    while end > start && bytes[end - 1].is_ascii_whitespace() {
+                        end -= 1;
+                    }
+                    return Some(end);
+                }
+                _ => {}
+            }
+        }
+        index += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sets_a_new_cargo_license_after_the_version() {
+        let content = "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\n";
+        assert_eq!(
+            set_cargo_license(content, "MIT").as_deref(),
+            Some("[package]\nname = \"app\"\nversion = \"0.1.0\"\nlicense = \"MIT\"\n\n[dependencies]\n")
+        );
+    }
+
+    #[test]
+    fn replaces_an_existing_cargo_license() {
+        let content = "[package]\nname = \"app\"\nlicense = \"MIT\"\n";
+        assert_eq!(
+            set_cargo_license(content, "Apache-2.0").as_deref(),
+            Some("[package]\nname = \"app\"\nlicense = \"Apache-2.0\"\n")
+        );
+        assert_eq!(set_cargo_license(content, "MIT"), None);
+    }
+
+    #[test]
+    fn leaves_inherited_and_file_licenses_alone() {
+        assert_eq!(set_cargo_license("[package]\nname = \"app\"\nlicense.workspace = true\n", "MIT"), None);
+        assert_eq!(set_cargo_license("[package]\nname = \"app\"\nlicense = { workspace = true }\n", "MIT"), None);
+        assert_eq!(set_cargo_license("[package]\nname = \"app\"\nlicense-file = \"LICENSE.txt\"\n", "MIT"), None);
+        assert_eq!(set_cargo_license("[package]\nname = \"app\"\n\n[package.license]\nworkspace = true\n", "MIT"), None);
+        assert_eq!(set_cargo_license("[workspace]\nmembers = [\"app\"]\n", "MIT"), None);
+    }
+
+    #[test]
+    fn sets_only_the_top_level_package_json_license() {
+        let content = "{\n  \"name\": \"app\",\n  \"publishConfig\": {\n    \"license\": \"MIT\"\n  },\n  \"license\": \"MIT\"\n}\n";
+        assert_eq!(
+            set_package_json_license(content, "Apache-2.0"),
+            Ok(Some(content.replace("\"license\": \"MIT\"\n}", "\"license\": \"Apache-2.0\"\n}")))
+        );
+
+        let content = "{\n  \"name\": \"app\",\n  \"version\": \"1.0.0\",\n  \"overrides\": { \"license\": \"MIT\" }\n}\n";
+        assert_eq!(
+            set_package_json_license(content, "MIT"),
+            Ok(Some("{\n  \"name\": \"app\",\n  \"version\": \"1.0.0\",\n  \"license\": \"MIT\",\n  \"overrides\": { \"license\": \"MIT\" }\n}\n".to_string()))
+        );
+    }
+
+    #[test]
+    fn sets_the_license_of_a_minified_package_json() {
+        assert_eq!(
+            set_package_json_license("{\"name\":\"app\",\"version\":\"1.0.0\"}", "MIT OR Apache-2.0"),
+            Ok(Some("{\"name\":\"app\",\"version\":\"1.0.0\",\"license\":\"MIT OR Apache-2.0\"}".to_string()))
+        );
+        assert_eq!(
+            set_package_json_license("{\"license\":\"MIT\",\"name\":\"app\"}", "ISC"),
+            Ok(Some("{\"license\":\"ISC\",\"name\":\"app\"}".to_string()))
+        );
+        assert_eq!(set_package_json_license("{\"name\":\"app\",\"license\":\"MIT\"}", "MIT"), Ok(None));
+        assert_eq!(set_package_json_license("{}", "MIT"), Ok(Some("{\"license\": \"MIT\"}".to_string())));
+    }
+
+    #[test]
+    fn reports_package_json_files_it_cannot_update() {
+        assert!(set_package_json_license("{\"name\": \"app\",", "MIT").is_err());
+        assert!(set_package_json_license("[\"app\"]", "MIT").is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use tokio::time::Instant;
+
+// The RateLimiter is shared by all concurrent requests against one API. When a response
+// tells that the rate limit is exhausted (or a Retry-After header is present), every
+// following request waits until the API accepts requests again.
+
+static RATE_LIMIT_REMAINING: &str = "x-ratelimit-remaining";
+static RATE_LIMIT_RESET: &str = "x-ratelimit-reset";
+
+/// The base delay of the exponential backoff between retries.
+static BACKOFF_BASE: Duration = Duration::from_millis(500);
+/// The maximum random delay added to every backoff.
+static MAX_JITTER_MILLIS: u32 = 250;
+
+/// Keeps track of the point in time when the API accepts requests again.
+pub struct RateLimiter {
+    resume_at: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    /// Initializes a new `RateLimiter` that does not hold back any request.
+    pub const fn new() -> Self {
+        Self {
+            resume_at: Mutex::new(None),
+        }
+    }
+
+    /// Waits until the API accepts requests again.
+    pub async fn wait(&self) {
+        let resume_at = *self.resume_at.lock().unwrap();
+        if let Some(instant) = resume_at {
+            tokio::time::sleep_until(instant).await;
+        }
+    }
+
+    /// Holds back all requests for the given duration.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - How long no request should be sent.
+    pub fn pause_for(&self, duration: Duration) {
+        let until = Instant::now() + duration;
+        let mut resume_at = self.resume_at.lock().unwrap();
+        if resume_at.is_none_or(|current| current < until) {
+            *resume_at = Some(until);
+        }
+    }
+
+    /// Reads the rate limit headers of a response.
+    ///
+    /// # Arguments
+    ///
+    /// * `headers` - The headers of the response.
+    ///
+    /// # Returns
+    ///
+    /// The duration until the rate limit resets, if the remaining requests are used up.
+    pub fn exhausted_for(headers: &HeaderMap) -> Option<Duration> {
+        let remaining = header_as_u64(headers, RATE_LIMIT_REMAINING)?;
+        if remaining > 0 {
+            return None;
+        }
+        let reset = RateLimiter::reset_timestamp(headers)?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+        Some(Duration::from_secs(reset.saturating_sub(now) + 1))
+    }
+
+    /// Reads the `X-RateLimit-Reset` header of a response, the reset time in seconds since the epoch.
+    ///
+    /// # Arguments
+    ///
+    /// * `headers` - The headers of the response.
+    pub fn reset_timestamp(headers: &HeaderMap) -> Option<u64> {
+        header_as_u64(headers, RATE_LIMIT_RESET)
+    }
+
+    /// Reads the `Retry-After` header of a response, given in seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `headers` - The headers of the response.
+    pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+        header_as_u64(headers, RETRY_AFTER.as_str()).map(Duration::from_secs)
+    }
+
+    /// Returns the exponential backoff delay for a retry, with a random jitter.
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt` - The number of the retry, starting at 0.
+    pub fn backoff_with_jitter(attempt: u32) -> Duration {
+        let jitter = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.subsec_nanos() % MAX_JITTER_MILLIS)
+            .unwrap_or_default();
+        BACKOFF_BASE * 2u32.pow(attempt) + Duration::from_millis(jitter as u64)
+    }
+}
+
+/// Parses a header value as unsigned integer.
+///
+/// # Arguments
+///
+/// * `headers` - The headers of the response.
+/// * `name` - The name of the header.
+fn header_as_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse::<u64>().ok()
+}
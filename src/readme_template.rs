@@ -0,0 +1,241 @@
+use std::path::Path;
+
+use chrono::{Datelike, Utc};
+
+use crate::api_communicator::download_readme_template;
+use crate::git_dir::GitDir;
+use crate::git_metadata::{author_name, description, remote_url};
+use crate::project_metadata::{language_key, ProjectMetadata};
+use crate::settings_file::ProgramSettings;
+
+// README templates can use these variables, written like {{project}}:
+//
+// project, description, author, license_name, license_link, year, remote_url, language,
+// build_commands, test_commands
+//
+// Variables without a value are replaced with an empty string, unknown ones are kept as they are.
+// The commands are rendered as a fenced shell block.
+
+/// The built-in template, used if `readme_template_link` is empty or cannot be downloaded.
+static BUILT_IN_TEMPLATE: &str = include_str!("../resources/readme_template.md");
+
+/// The name of the template used from a template directory, if there is none for the language.
+static DEFAULT_TEMPLATE_NAME: &str = "default";
+
+/// The values for the variables of a README template.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateVariables {
+    project: String,
+    description: String,
+    author: String,
+    license_name: String,
+    license_link: String,
+    year: String,
+    remote_url: String,
+    language: String,
+    build_commands: String,
+    test_commands: String,
+}
+
+impl TemplateVariables {
+    /// Resolves the variables from the repository, its manifest files and its git metadata.
+    ///
+    /// The description of `.git/description` wins over the one of a manifest.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - The repository the README is created for.
+    /// * `program_settings` - The program settings, the `github_user` is the fallback author.
+    pub async fn resolve(directory: &GitDir, program_settings: &ProgramSettings) -> Self {
+        let metadata = ProjectMetadata::infer(&directory.path).await;
+        Self {
+            project: directory.project_title.clone(),
+            description: description(&directory.path)
+                .await
+                .or(metadata.description)
+                .unwrap_or_default(),
+            author: author_name(&directory.path)
+                .await
+                .unwrap_or_else(|| program_settings.github_user.clone()),
+            license_name: directory
+                .license
+                .as_ref()
+                .map(|license| license.name.clone())
+                .unwrap_or_default(),
+            license_link: directory
+                .license
+                .as_ref()
+                .map(|license| directory.license_link(program_settings, license))
+                .unwrap_or_default(),
+            year: Utc::now().year().to_string(),
+            remote_url: remote_url(&directory.path, "origin").await.unwrap_or_default(),
+            language: metadata.language.unwrap_or_default(),
+            build_commands: shell_block(&metadata.build_commands),
+            test_commands: shell_block(&metadata.test_commands),
+        }
+    }
+
+    /// Replaces all known variables in the template.
+    ///
+    /// The template is rendered in a single pass, so values that contain `{{...}}` (like a description
+    /// about templates) are kept as they are. Unknown variables are left in the README.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The README template.
+    ///
+    /// # Returns
+    ///
+    /// The rendered README.
+    pub fn render(&self, template: &str) -> String {
+        let variables = [
+            ("project", &self.project),
+            ("description", &self.description),
+            ("author", &self.author),
+            ("license_name", &self.license_name),
+            ("license_link", &self.license_link),
+            ("year", &self.year),
+            ("remote_url", &self.remote_url),
+            ("language", &self.language),
+            ("build_commands", &self.build_commands),
+            ("test_commands", &self.test_commands),
+        ];
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            rendered.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let value = after
+                .find("}}")
+                .and_then(|end| variables.iter().find(|(name, _)| *name == &after[..end]).map(|(name, value)| (name.len(), value)));
+            match value {
+                Some((name_length, value)) => {
+                    rendered.push_str(value);
+                    rest = &after[name_length + 2..];
+                }
+                // Not a variable, but the next brace may start one, like in "{{{project}}}".
+                None => {
+                    rendered.push('{');
+                    rest = &rest[start + 1..];
+                }
+            }
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+}
+
+/// Renders commands as a fenced shell block.
+///
+/// # Arguments
+///
+/// * `commands` - The commands, one per line.
+///
+/// # Returns
+///
+/// The fenced block, or an empty string if there are no commands.
+fn shell_block(commands: &[String]) -> String {
+    if commands.is_empty() {
+        String::new()
+    } else {
+        format!("```sh\n{}\n```", commands.join("\n"))
+    }
+}
+
+/// Loads a README template from a local file or directory.
+///
+/// In a directory, the template for the language of the repository (like `rust.md` or `csharp.md`)
+/// is preferred over `default.md`.
+///
+/// # Arguments
+///
+/// * `path` - The path of the template file or directory.
+/// * `language` - The detected language of the repository, if any.
+///
+/// # Returns
+///
+/// The content of the template, or `None` if no template could be read.
+async fn load_local_template(path: &str, language: &str) -> Option<String> {
+    let template_path = Path::new(path);
+    if !template_path.is_dir() {
+        return tokio::fs::read_to_string(template_path).await.ok();
+    }
+    let mut candidates: Vec<String> = vec![];
+    if !language.is_empty() {
+        candidates.push(language_key(language));
+    }
+    candidates.push(DEFAULT_TEMPLATE_NAME.to_string());
+
+    let mut entries = tokio::fs::read_dir(template_path).await.ok()?;
+    let mut files: Vec<std::path::PathBuf> = vec![];
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        files.push(entry.path());
+    }
+    for candidate in candidates {
+        if let Some(file) = files.iter().find(|file| {
+            file.file_stem()
+                .map(|stem| stem.to_string_lossy().eq_ignore_ascii_case(&candidate))
+                .unwrap_or(false)
+        }) {
+            return tokio::fs::read_to_string(file).await.ok();
+        }
+    }
+    None
+}
+
+/// Creates the content of a new README for a repository.
+///
+/// The template is read from `readme_template_path` if it is set, otherwise it is downloaded
+/// from `readme_template_link`. If the link is empty or the download fails, the built-in template
+/// is used. The `replace_in_readme_phrase` is replaced with the project title,
+/// then all template variables are rendered.
+///
+/// # Arguments
+///
+/// * `program_settings` - The program settings.
+/// * `directory` - The repository the README is created for.
+///
+/// # Returns
+///
+/// The README content, or `None` if no template could be loaded.
+pub async fn create_readme(program_settings: &ProgramSettings, directory: &GitDir) -> Option<String> {
+    let variables = TemplateVariables::resolve(directory, program_settings).await;
+    let template = match &program_settings.readme_template_path {
+        Some(path) => load_local_template(path, &variables.language).await?,
+        None if program_settings.readme_template_link.is_empty() => BUILT_IN_TEMPLATE.to_string(),
+        None => download_readme_template(program_settings)
+            .await
+            .unwrap_or_else(|| BUILT_IN_TEMPLATE.to_string()),
+    };
+    let template = if program_settings.replace_in_readme_phrase.is_empty() {
+        template
+    } else {
+        template.replace(&program_settings.replace_in_readme_phrase, &directory.project_title)
+    };
+    Some(variables.render(&template))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_all_known_variables() {
+        let variables = TemplateVariables { project: "app".to_string(), year: "2026".to_string(), ..TemplateVariables::default() };
+        assert_eq!(variables.render("# {{project}}\n\n(c) {{year}} {{unknown}} {{{project}}} {{project"), "# app\n\n(c) 2026 {{unknown}} {app} {{project");
+    }
+
+    #[test]
+    fn keeps_placeholders_inside_of_values() {
+        let variables = TemplateVariables {
+            project: "{{year}}".to_string(),
+            description: "Renders {{project}} and {{description}}".to_string(),
+            year: "2026".to_string(),
+            ..TemplateVariables::default()
+        };
+        assert_eq!(
+            variables.render("# {{project}}\n{{description}}\n{{year}}"),
+            "# {{year}}\nRenders {{project}} and {{description}}\n2026"
+        );
+    }
+}
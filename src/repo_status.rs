@@ -0,0 +1,208 @@
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::git_command::{git_output, run_git};
+use crate::output_printer::PrintMode;
+
+// Before license-me touches a repository, it checks the state of it, so it does not overwrite
+// uncommitted edits of the LICENSE or README, or acts in the middle of a merge or rebase.
+
+/// The names (in uppercase) the files license-me writes start with, the licenses, READMEs and community files.
+static GUARDED_FILE_PREFIXES: [&str; 8] = [
+    "LICENSE",
+    "LICENCE",
+    "COPYING",
+    "README",
+    "CONTRIBUTING",
+    "CODE_OF_CONDUCT",
+    "SECURITY",
+    "NOTICE",
+];
+
+/// The names (in uppercase) of the manifests license-me writes the license into.
+static GUARDED_MANIFESTS: [&str; 2] = ["CARGO.TOML", "PACKAGE.JSON"];
+
+/// The directories (in uppercase) besides the root, license-me writes community files into.
+static GUARDED_DIRECTORIES: [&str; 2] = [".GITHUB", "DOCS"];
+
+/// The pathspecs of `git status` for the files license-me writes: the root, `.github` and `docs`.
+static GUARDED_PATHSPECS: [&str; 3] = [":(glob,icase)*", ":(glob,icase).github/**", ":(glob,icase)docs/**"];
+
+/// What happens with repositories in an unclean state.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UncleanRepositoryPolicy {
+    /// Skips the repository and lists it in the summary.
+    #[default]
+    Skip,
+    /// Prints a warning and works on the repository anyway.
+    Warn,
+    /// Does not check the state of the repository.
+    Ignore,
+}
+
+/// A reason why a repository is not in a clean state.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RepositoryIssue {
+    /// The files license-me writes, like the LICENSE, README or manifests, have uncommitted changes.
+    UncommittedChanges(Vec<String>),
+    /// A merge is in progress.
+    MergeInProgress,
+    /// A rebase is in progress.
+    RebaseInProgress,
+    /// HEAD does not point at a branch.
+    DetachedHead,
+    /// git could not read the state of the repository.
+    UnreadableState(String),
+}
+
+impl Display for RepositoryIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UncommittedChanges(files) => write!(f, "uncommitted changes in {}", files.join(", ")),
+            Self::MergeInProgress => write!(f, "a merge is in progress"),
+            Self::RebaseInProgress => write!(f, "a rebase is in progress"),
+            Self::DetachedHead => write!(f, "HEAD is detached"),
+            Self::UnreadableState(error) => write!(f, "its state could not be read ({})", error),
+        }
+    }
+}
+
+/// A repository that was skipped, with the reasons.
+#[derive(Clone, Debug)]
+pub struct SkippedRepository {
+    /// The path of the repository.
+    pub(crate) path: String,
+    /// Why the repository was skipped.
+    pub(crate) issues: Vec<RepositoryIssue>,
+}
+
+impl SkippedRepository {
+    /// Returns the reasons as one line, like `a merge is in progress, HEAD is detached`.
+    pub fn reasons(&self) -> String {
+        self.issues.iter().map(ToString::to_string).collect::<Vec<String>>().join(", ")
+    }
+}
+
+/// Checks whether a file is one license-me writes: a license, README or community file in the root,
+/// `.github` or `docs`, or a manifest in the root.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file, relative to the repository.
+fn is_guarded_file(path: &str) -> bool {
+    let path = path.to_uppercase();
+    let is_written = |name: &str| GUARDED_FILE_PREFIXES.iter().any(|prefix| name.starts_with(prefix));
+    match path.split_once('/') {
+        None => is_written(&path) || GUARDED_MANIFESTS.contains(&path.as_str()),
+        Some((directory, name)) => GUARDED_DIRECTORIES.contains(&directory) && !name.contains('/') && is_written(name),
+    }
+}
+
+/// Returns the files license-me writes, that have uncommitted (or untracked) changes.
+///
+/// # Arguments
+///
+/// * `work_dir` - The path of the working tree.
+pub async fn changed_guarded_files(work_dir: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut arguments = vec!["status", "--porcelain", "-z", "--untracked-files=all", "--"];
+    arguments.extend(GUARDED_PATHSPECS);
+    let status = run_git(work_dir, &arguments).await?;
+    let mut entries = status.split('\0').filter(|entry| !entry.is_empty());
+    let mut files = Vec::new();
+    while let Some(entry) = entries.next() {
+        let (code, path) = entry.split_at(entry.len().min(3));
+        // Renames and copies are followed by the original path.
+        if code.starts_with('R') || code.starts_with('C') {
+            entries.next();
+        }
+        if is_guarded_file(path) {
+            files.push(path.to_string());
+        }
+    }
+    Ok(files)
+}
+
+/// Reads the state of a repository.
+///
+/// # Arguments
+///
+/// * `work_dir` - The path of the working tree.
+///
+/// # Returns
+///
+/// Everything that keeps the repository from being in a clean state, an empty list if it is clean.
+///
+/// # Errors
+///
+/// Returns an error if git cannot read the repository.
+pub async fn check_repository(work_dir: &str) -> Result<Vec<RepositoryIssue>, Box<dyn std::error::Error>> {
+    let mut issues = Vec::new();
+    let files = changed_guarded_files(work_dir).await?;
+    if !files.is_empty() {
+        issues.push(RepositoryIssue::UncommittedChanges(files));
+    }
+
+    let git_dir = run_git(work_dir, &["rev-parse", "--absolute-git-dir"]).await?;
+    let git_dir = Path::new(git_dir.trim());
+    if git_dir.join("MERGE_HEAD").exists() {
+        issues.push(RepositoryIssue::MergeInProgress);
+    }
+    if git_dir.join("rebase-merge").exists() || git_dir.join("rebase-apply").exists() {
+        issues.push(RepositoryIssue::RebaseInProgress);
+    }
+
+    if !git_output(work_dir, &["symbolic-ref", "-q", "HEAD"]).await?.status.success() {
+        issues.push(RepositoryIssue::DetachedHead);
+    }
+    Ok(issues)
+}
+
+/// Prints the repositories that were skipped, and why.
+///
+/// # Arguments
+///
+/// * `skipped` - The skipped repositories.
+/// * `pm` - The print mode.
+pub fn list_skipped(skipped: &[SkippedRepository], pm: &PrintMode) {
+    if skipped.is_empty() {
+        return;
+    }
+    pm.normal_msg(format!(
+        "\n\n{}",
+        ansi_term::Color::Yellow.bold().paint(format!("Skipped {} repositories:", skipped.len()))
+    ));
+    skipped.iter().for_each(|repository| pm.normal_msg(format!("  {}: {}", repository.path, repository.reasons())));
+}
+
+/// Checks a repository before license-me works on it, following the policy for unclean repositories.
+///
+/// # Arguments
+///
+/// * `work_dir` - The path of the working tree.
+/// * `policy` - What happens with unclean repositories.
+/// * `pm` - The print mode.
+///
+/// # Returns
+///
+/// The repository and its issues, if it has to be skipped.
+pub async fn guard_repository(work_dir: &str, policy: UncleanRepositoryPolicy, pm: &PrintMode) -> Option<SkippedRepository> {
+    if policy == UncleanRepositoryPolicy::Ignore {
+        return None;
+    }
+    // A repository whose state cannot be read could be in any state.
+    let issues = match check_repository(work_dir).await {
+        Ok(issues) if !issues.is_empty() => issues,
+        Ok(_) => return None,
+        Err(error) => vec![RepositoryIssue::UnreadableState(error.to_string().trim().to_string())],
+    };
+    let repository = SkippedRepository { path: work_dir.to_string(), issues };
+    if policy == UncleanRepositoryPolicy::Warn {
+        pm.normal_msg(format!("{} {}: {}", ansi_term::Color::Yellow.bold().paint("Warning:"), work_dir, repository.reasons()));
+        return None;
+    }
+    pm.normal_msg(format!("Skipping {}: {}", work_dir, repository.reasons()));
+    Some(repository)
+}
@@ -0,0 +1,231 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, MAIN_SEPARATOR};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::git_dir::GitDir;
+use crate::git_metadata::{common_dir_of, git_dir_of};
+use crate::github_license::GithubLicense;
+use crate::output_printer::PrintMode;
+use crate::repository_kind::RepositoryKind;
+use crate::settings_file::ProgramSettings;
+use crate::walk_filter::WalkFilter;
+
+// Searching all disks takes minutes, while most runs look at the same repositories again.
+// The index keeps the repositories of the last complete search with what was detected in them, and when
+// they were modified, so the next run only reads the repositories that changed since (--rescan searches again).
+// What was detected also depends on the licenses it was compared with, so the index remembers a fingerprint of them.
+
+/// The name of the index file, next to the settings file.
+static INDEX_FILE_NAME: &str = "repository-index.json";
+
+/// A repository in the index.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    /// The last modification of the repository when it was read, in milliseconds since the epoch.
+    pub(crate) modified: u64,
+    /// What was detected in the repository.
+    pub(crate) repository: GitDir,
+}
+
+/// The repositories found by the last complete search.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepositoryIndex {
+    /// The filter of the search, another filter finds other repositories.
+    pub(crate) filter: WalkFilter,
+    /// The fingerprint of the licenses and settings the repositories were analysed with, see `detection_fingerprint`.
+    #[serde(default)]
+    pub(crate) fingerprint: u64,
+    /// The found repositories.
+    pub(crate) entries: Vec<IndexEntry>,
+}
+
+impl RepositoryIndex {
+    /// Creates the index of the repositories a search found.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - The filter of the search.
+    /// * `fingerprint` - The fingerprint of the licenses and settings the repositories were analysed with.
+    /// * `dirs` - The found repositories, before worktrees and nested repositories were attributed.
+    pub async fn of(filter: WalkFilter, fingerprint: u64, dirs: &[GitDir]) -> Self {
+        let mut entries: Vec<IndexEntry> = Vec::with_capacity(dirs.len());
+        for dir in dirs {
+            if let Some(modified) = last_modified(dir).await {
+                entries.push(IndexEntry { modified, repository: dir.clone() });
+            }
+        }
+        Self { filter, fingerprint, entries }
+    }
+
+    /// Sorts the entries by whether they can be taken as they are.
+    ///
+    /// An entry is outdated, if its repository was modified since it was indexed,
+    /// or if it was analysed with other licenses or settings (another fingerprint).
+    ///
+    /// # Arguments
+    ///
+    /// * `fingerprint` - The fingerprint of the licenses and settings of the current run.
+    ///
+    /// # Returns
+    ///
+    /// The up to date repositories, the found paths of the outdated ones (to analyse them again)
+    /// and the number of repositories that do not exist anymore.
+    pub async fn check_entries(self, fingerprint: u64) -> (Vec<GitDir>, Vec<String>, usize) {
+        let mut up_to_date: Vec<GitDir> = vec![];
+        let mut outdated: Vec<String> = vec![];
+        let mut removed = 0;
+        for entry in self.entries {
+            match last_modified(&entry.repository).await {
+                Some(modified) if modified == entry.modified && self.fingerprint == fingerprint => {
+                    up_to_date.push(entry.repository)
+                }
+                Some(_) => outdated.push(found_path(&entry.repository)),
+                None => removed += 1,
+            }
+        }
+        (up_to_date, outdated, removed)
+    }
+
+    /// Reads the index file.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - The filter of the current search.
+    ///
+    /// # Returns
+    ///
+    /// The index, or `None` if there is no valid, non-empty index of a search with the same filter.
+    pub async fn read(filter: &WalkFilter) -> Option<Self> {
+        let content = tokio::fs::read_to_string(index_file_path()).await.ok()?;
+        serde_json::from_str::<Self>(&content)
+            .ok()
+            .filter(|index| !index.entries.is_empty() && &index.filter == filter)
+    }
+
+    /// Writes the index file.
+    ///
+    /// # Arguments
+    ///
+    /// * `print_mode` - The print mode, for errors.
+    pub async fn write(&self, print_mode: &mut PrintMode) {
+        let path = index_file_path();
+        match serde_json::to_string(self) {
+            Ok(content) => {
+                if let Err(err) = tokio::fs::write(&path, content).await {
+                    print_mode.error_msg(format!("{} occurred while writing the repository index {}", err, path));
+                }
+            }
+            Err(err) => print_mode.error_msg(format!("{} occurred while serializing the repository index", err)),
+        }
+    }
+}
+
+/// Returns the fingerprint of what the detection in a repository depends on: the licenses it is compared with,
+/// the settings they come from and the version of license-me.
+///
+/// # Arguments
+///
+/// * `licenses` - The licenses the repositories are analysed with.
+/// * `settings` - The program settings.
+pub fn detection_fingerprint(licenses: &[GithubLicense], settings: &ProgramSettings) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    serde_json::to_string(&settings.license_sources).unwrap_or_default().hash(&mut hasher);
+    settings.custom_licenses_directory.hash(&mut hasher);
+    for license in licenses {
+        serde_json::to_string(license).unwrap_or_default().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Returns the path of the index file.
+fn index_file_path() -> String {
+    ProgramSettings::get_data_file_path(INDEX_FILE_NAME)
+}
+
+/// Returns the path the walker finds for a repository: the `.git` entry of a working tree, or the bare repository.
+///
+/// # Arguments
+///
+/// * `dir` - The repository.
+pub fn found_path(dir: &GitDir) -> String {
+    match dir.kind {
+        RepositoryKind::Bare => dir.path.clone(),
+        _ => format!("{}{}.git", dir.path, MAIN_SEPARATOR),
+    }
+}
+
+/// Returns when the repository was last modified.
+///
+/// This is the newest modification of the working tree, its git directory (which changes with every commit),
+/// the shared git directory of worktrees (which changes with the remotes) and the README and LICENSE files.
+///
+/// # Arguments
+///
+/// * `dir` - The repository.
+///
+/// # Returns
+///
+/// The milliseconds since the epoch, `None` if the repository does not exist anymore.
+pub async fn last_modified(dir: &GitDir) -> Option<u64> {
+    let mut modified = modified_millis(Path::new(&dir.path)).await?;
+    let paths = [
+        Some(git_dir_of(&dir.path).await),
+        Some(common_dir_of(&dir.path).await),
+        dir.readme_path.clone(),
+        dir.license_path.clone(),
+    ];
+    for path in paths.into_iter().flatten() {
+        modified = modified.max(modified_millis(&path).await.unwrap_or_default());
+    }
+    Some(modified)
+}
+
+/// Returns the modification time of a file or directory in milliseconds since the epoch.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file or directory.
+async fn modified_millis(path: &Path) -> Option<u64> {
+    let modified = tokio::fs::metadata(path).await.ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn license(body: &str) -> GithubLicense {
+        GithubLicense { key: "mit".to_string(), spdx_id: "MIT".to_string(), body: body.to_string(), ..GithubLicense::default() }
+    }
+
+    #[test]
+    fn fingerprint_changes_with_the_licenses_and_sources() {
+        let settings = ProgramSettings::default();
+        let fingerprint = detection_fingerprint(&[license("MIT License")], &settings);
+        assert_eq!(fingerprint, detection_fingerprint(&[license("MIT License")], &settings));
+        assert_ne!(fingerprint, detection_fingerprint(&[license("MIT License, changed")], &settings));
+        assert_ne!(fingerprint, detection_fingerprint(&[], &settings));
+
+        let settings = ProgramSettings { custom_licenses_directory: Some("licenses".to_string()), ..ProgramSettings::default() };
+        assert_ne!(fingerprint, detection_fingerprint(&[license("MIT License")], &settings));
+    }
+
+    #[tokio::test]
+    async fn entries_of_another_fingerprint_are_outdated() {
+        let repository = std::env::temp_dir().join("license-me-test-index-fingerprint");
+        let _ = std::fs::remove_dir_all(&repository);
+        std::fs::create_dir_all(repository.join(".git")).unwrap();
+        let dir = GitDir::init(format!("{}{}.git", repository.display(), MAIN_SEPARATOR), Some(&[])).await;
+        let index = RepositoryIndex::of(WalkFilter::default(), 1, std::slice::from_ref(&dir)).await;
+
+        let (up_to_date, outdated, removed) = index.clone().check_entries(1).await;
+        assert_eq!((up_to_date.len(), outdated.len(), removed), (1, 0, 0));
+        let (up_to_date, outdated, removed) = index.check_entries(2).await;
+        assert_eq!((up_to_date.len(), outdated, removed), (0, vec![found_path(&dir)], 0));
+
+        std::fs::remove_dir_all(&repository).unwrap();
+    }
+}
@@ -0,0 +1,190 @@
+use std::fmt::{Display, Formatter};
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::git_dir::GitDir;
+use crate::git_metadata::{common_dir_of, git_dir_of, is_file};
+
+// Not every ".git" belongs to a plain working tree: worktrees and submodules have a ".git" file
+// pointing at their git directory, bare repositories have no working tree at all, and repositories
+// can be cloned into the working tree of another one.
+
+/// The kind of a repository the walker found.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RepositoryKind {
+    /// A working tree with its own `.git` directory.
+    #[default]
+    Regular,
+    /// A linked worktree, created by `git worktree add`.
+    Worktree {
+        /// The main working tree (or the bare repository) the worktree belongs to.
+        main: String,
+    },
+    /// A submodule of another repository.
+    Submodule {
+        /// The working tree of the superproject.
+        parent: String,
+    },
+    /// A repository inside the working tree of another one, that is not a submodule.
+    Nested {
+        /// The working tree of the enclosing repository.
+        parent: String,
+    },
+    /// A bare repository, like `project.git`, without a working tree to edit.
+    Bare,
+}
+
+impl RepositoryKind {
+    /// Detects the kind of a repository from its `.git` entry.
+    ///
+    /// Nested repositories can only be told apart, once all repositories are found, see `attribute_repositories`.
+    ///
+    /// # Arguments
+    ///
+    /// * `work_dir` - The path of the working tree, or of the bare repository.
+    pub async fn detect(work_dir: &str) -> Self {
+        let dot_git = Path::new(work_dir).join(".git");
+        match tokio::fs::metadata(&dot_git).await {
+            Ok(metadata) if metadata.is_dir() => return Self::Regular,
+            Ok(metadata) if metadata.is_file() => {}
+            _ => {
+                let work_dir = PathBuf::from(work_dir);
+                let is_bare = tokio::task::spawn_blocking(move || is_bare_repository(&work_dir)).await.unwrap_or(false);
+                return if is_bare { Self::Bare } else { Self::Regular };
+            }
+        }
+
+        let git_dir = git_dir_of(work_dir).await;
+        if is_file(&git_dir.join("commondir")).await {
+            let common_dir = canonical_async(&common_dir_of(work_dir).await).await;
+            let main = match common_dir.file_name() {
+                Some(name) if name == ".git" => common_dir.parent().map(Path::to_path_buf).unwrap_or(common_dir),
+                _ => common_dir,
+            };
+            return Self::Worktree { main: main.display().to_string() };
+        }
+        match superproject_of(&canonical_async(&git_dir).await) {
+            Some(parent) => Self::Submodule { parent: parent.display().to_string() },
+            None => Self::Regular,
+        }
+    }
+
+    /// Checks whether the repository has a working tree license-me can edit.
+    pub fn has_work_tree(&self) -> bool {
+        self != &Self::Bare
+    }
+}
+
+impl Display for RepositoryKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Regular => Ok(()),
+            Self::Worktree { main } => write!(f, "worktree of {}", main),
+            Self::Submodule { parent } => write!(f, "submodule of {}", parent),
+            Self::Nested { parent } => write!(f, "nested in {}", parent),
+            Self::Bare => write!(f, "bare"),
+        }
+    }
+}
+
+/// Returns the canonical form of a path, or the path itself if it does not exist.
+///
+/// # Arguments
+///
+/// * `path` - The path.
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Returns the canonical form of a path like `canonical`, without blocking the runtime.
+///
+/// # Arguments
+///
+/// * `path` - The path.
+async fn canonical_async(path: &Path) -> PathBuf {
+    tokio::fs::canonicalize(path).await.unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Checks whether a directory is a bare repository, with `HEAD`, `objects` and `refs` but no working tree.
+///
+/// # Arguments
+///
+/// * `path` - The path of the directory.
+pub fn is_bare_repository(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name != ".git")
+        && path.join("HEAD").is_file()
+        && path.join("objects").is_dir()
+        && path.join("refs").is_dir()
+}
+
+/// Returns the working tree of the superproject, if a git directory lies in its `.git/modules`.
+///
+/// # Arguments
+///
+/// * `git_dir` - The canonical git directory of the submodule.
+fn superproject_of(git_dir: &Path) -> Option<PathBuf> {
+    let components = git_dir.components().collect::<Vec<Component>>();
+    let modules_at = components
+        .windows(2)
+        .position(|pair| pair[0].as_os_str() == ".git" && pair[1].as_os_str() == "modules")?;
+    Some(components[..modules_at].iter().collect())
+}
+
+/// Attributes the found repositories to each other: nested repositories get their enclosing repository,
+/// and the worktrees of a repository are listed once, at the main working tree.
+///
+/// # Arguments
+///
+/// * `dirs` - All repositories the walker found.
+///
+/// # Returns
+///
+/// The repositories without duplicated worktrees.
+pub fn attribute_repositories(dirs: Vec<GitDir>) -> Vec<GitDir> {
+    let canonical_paths = dirs.iter().map(|dir| canonical(Path::new(&dir.path))).collect::<Vec<PathBuf>>();
+    let mut attributed: Vec<GitDir> = Vec::with_capacity(dirs.len());
+    let mut attributed_paths: Vec<PathBuf> = Vec::with_capacity(dirs.len());
+
+    for (mut dir, path) in dirs.into_iter().zip(canonical_paths.iter()) {
+        if dir.kind == RepositoryKind::Regular {
+            // The closest enclosing repository is the parent.
+            if let Some(parent) = canonical_paths
+                .iter()
+                .filter(|other| *other != path && path.starts_with(other))
+                .max_by_key(|other| other.components().count())
+            {
+                dir.kind = RepositoryKind::Nested { parent: parent.display().to_string() };
+            }
+        }
+        attributed.push(dir);
+        attributed_paths.push(path.clone());
+    }
+
+    // Worktrees go to their main working tree, or to the first worktree if the main one was not found.
+    let mut deduplicated: Vec<GitDir> = Vec::with_capacity(attributed.len());
+    let mut deduplicated_keys: Vec<PathBuf> = Vec::with_capacity(attributed.len());
+    let mut worktrees: Vec<GitDir> = vec![];
+    for (dir, path) in attributed.into_iter().zip(attributed_paths) {
+        if let RepositoryKind::Worktree { .. } = dir.kind {
+            worktrees.push(dir);
+        } else {
+            deduplicated.push(dir);
+            deduplicated_keys.push(path);
+        }
+    }
+    for worktree in worktrees {
+        let RepositoryKind::Worktree { main } = &worktree.kind else {
+            continue;
+        };
+        let main = PathBuf::from(main);
+        match deduplicated_keys.iter().position(|key| key == &main) {
+            Some(position) => deduplicated[position].worktrees.push(worktree.path.clone()),
+            None => {
+                deduplicated.push(worktree);
+                deduplicated_keys.push(main);
+            }
+        }
+    }
+    deduplicated
+}
@@ -6,7 +6,11 @@ use std::string::ToString;
 use serde::{Deserialize, Serialize};
 use tokio::io::AsyncWriteExt;
 
+use crate::license_link_style::LicenseLinkStyle;
+use crate::license_source::{deserialize_license_sources, LicenseSource};
 use crate::output_printer::PrintMode;
+use crate::repo_status::UncleanRepositoryPolicy;
+use crate::walk_filter::{DEFAULT_ALLOWED_DIRECTORIES, DEFAULT_PRUNED_DIRECTORIES, DEFAULT_SYSTEM_DIRECTORIES};
 
 #[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ProgramSettings {
@@ -14,6 +18,138 @@ pub struct ProgramSettings {
     pub(super) github_api_token: Option<String>,
     pub(super) readme_template_link: String,
     pub(super) replace_in_readme_phrase: String,
+    #[serde(default)]
+    pub(super) readme_template_path: Option<String>,
+    #[serde(default)]
+    pub(super) community_templates_directory: Option<String>,
+    #[serde(default)]
+    pub(super) license_link_style: LicenseLinkStyle,
+    #[serde(default)]
+    pub(super) license_badge: bool,
+    #[serde(default = "default_license_badge_url")]
+    pub(super) license_badge_url: String,
+    #[serde(default)]
+    pub(super) custom_licenses_directory: Option<String>,
+    #[serde(default = "default_license_sources", alias = "license_source", deserialize_with = "deserialize_license_sources")]
+    pub(super) license_sources: Vec<LicenseSource>,
+    #[serde(default = "default_api_base_url")]
+    pub(super) api_base_url: String,
+    #[serde(default = "default_api_version")]
+    pub(super) api_version: Option<String>,
+    #[serde(default)]
+    pub(super) ca_bundle_path: Option<String>,
+    #[serde(default)]
+    pub(super) http_proxy: Option<String>,
+    #[serde(default)]
+    pub(super) https_proxy: Option<String>,
+    #[serde(default)]
+    pub(super) no_proxy: Option<String>,
+    #[serde(default = "default_update_manifests")]
+    pub(super) update_manifests: bool,
+    #[serde(default = "default_commit_message_template")]
+    pub(super) commit_message_template: String,
+    #[serde(default = "default_community_commit_message")]
+    pub(super) community_commit_message: String,
+    #[serde(default)]
+    pub(super) commit_author: Option<String>,
+    #[serde(default = "default_branch_name_template")]
+    pub(super) branch_name_template: String,
+    #[serde(default)]
+    pub(super) unclean_repository_policy: UncleanRepositoryPolicy,
+    #[serde(default)]
+    pub(super) owned_owners: Vec<String>,
+    #[serde(default)]
+    pub(super) include_patterns: Vec<String>,
+    #[serde(default)]
+    pub(super) exclude_patterns: Vec<String>,
+    #[serde(default = "default_pruned_directories")]
+    pub(super) pruned_directories: Vec<String>,
+    #[serde(default)]
+    pub(super) find_nested_repositories: bool,
+    #[serde(default = "default_skip_hidden_directories")]
+    pub(super) skip_hidden_directories: bool,
+    #[serde(default = "default_skipped_system_directories")]
+    pub(super) skipped_system_directories: Vec<String>,
+    #[serde(default = "default_allowed_directories")]
+    pub(super) allowed_directories: Vec<String>,
+    #[serde(skip)]
+    pub(super) commit_changes: bool,
+    #[serde(skip)]
+    pub(super) commit_signoff: bool,
+    #[serde(skip)]
+    pub(super) commit_on_branch: bool,
+    #[serde(skip)]
+    pub(super) patch_directory: Option<String>,
+    #[serde(skip)]
+    pub(super) include_foreign: bool,
+    #[serde(skip)]
+    pub(super) preselected_license: Option<String>,
+    #[serde(skip)]
+    pub(super) preselected_exception: Option<String>,
+    #[serde(skip)]
+    pub(super) refresh_licenses: bool,
+    #[serde(skip)]
+    pub(super) rescan: bool,
+}
+
+/// The base URL of the public GitHub API. GitHub Enterprise Server uses `https://<host>/api/v3`.
+fn default_api_base_url() -> String {
+    "https://api.github.com".to_string()
+}
+
+/// The URL pattern of the license badge, pointing at shields.io.
+fn default_license_badge_url() -> String {
+    "https://img.shields.io/badge/license-{{shields_spdx_id}}-blue.svg".to_string()
+}
+
+/// Manifests like `Cargo.toml` only get the license expression, if the user opts in.
+fn default_update_manifests() -> bool {
+    false
+}
+
+/// The message of the commit with the license changes, see `OperatingMode::commit_action` for `{action}`.
+fn default_commit_message_template() -> String {
+    "chore: {action} {spdx} license".to_string()
+}
+
+/// The message of the commit with the community files.
+fn default_community_commit_message() -> String {
+    "docs: add community files".to_string()
+}
+
+/// The name of the branch with the license changes, `{spdx}` is lowercase and without spaces, like `mit-or-apache-2.0`.
+fn default_branch_name_template() -> String {
+    "license-me/{action}-{spdx}".to_string()
+}
+
+/// The dependency, cache and build directories the walker does not look into.
+fn default_pruned_directories() -> Vec<String> {
+    DEFAULT_PRUNED_DIRECTORIES.iter().map(ToString::to_string).collect()
+}
+
+/// Hidden directories, like `~/.cache`, are skipped by default.
+fn default_skip_hidden_directories() -> bool {
+    true
+}
+
+/// The system directories the walker does not look into.
+fn default_skipped_system_directories() -> Vec<String> {
+    DEFAULT_SYSTEM_DIRECTORIES.iter().map(ToString::to_string).collect()
+}
+
+/// The hidden directories that are searched anyway, for dotfiles repositories.
+fn default_allowed_directories() -> Vec<String> {
+    DEFAULT_ALLOWED_DIRECTORIES.iter().map(ToString::to_string).collect()
+}
+
+/// The GitHub API version sent in the `X-GitHub-Api-Version` header.
+fn default_api_version() -> Option<String> {
+    Some("2022-11-28".to_string())
+}
+
+/// The license sources used if the settings file does not name any: only the GitHub API.
+fn default_license_sources() -> Vec<LicenseSource> {
+    vec![LicenseSource::GithubApi]
 }
 
 impl Default for ProgramSettings {
@@ -22,8 +158,28 @@ impl Default for ProgramSettings {
     /// The default configuration consists of:
     /// - An empty `github_user` string.
     /// - An optional `github_api_token` that is set to `None`.
-    /// - A `readme_template_link` string that is set to "https://raw.githubusercontent.com/PurpleBooth/a-good-readme-template/main/README.md".
+    /// - An empty `readme_template_link`, so the built-in template (with the inferred description, build and
+    ///   test commands) is used.
     /// - A `replace_in_readme_phrase` string that is set to "# Project Title".
+    /// - An optional `readme_template_path` that is set to `None`.
+    /// - An optional `community_templates_directory` that is set to `None`.
+    /// - A `license_link_style` that is set to `External`.
+    /// - A `license_badge` flag that is set to `false`.
+    /// - A `license_badge_url` that points at shields.io.
+    /// - An optional `custom_licenses_directory` that is set to `None`.
+    /// - A `license_sources` list that only contains the GitHub API.
+    /// - An `api_base_url` that is set to "https://api.github.com" and an `api_version` set to "2022-11-28".
+    /// - Optional `ca_bundle_path`, `http_proxy`, `https_proxy` and `no_proxy` that are set to `None`.
+    /// - An `update_manifests` flag that is set to `false`.
+    /// - A `commit_message_template` set to "chore: {action} {spdx} license", a `community_commit_message`
+    ///   set to "docs: add community files" and an optional `commit_author` that is set to `None`.
+    /// - A `branch_name_template` that is set to "license-me/{action}-{spdx}".
+    /// - An `unclean_repository_policy` that is set to `Skip`.
+    /// - An empty `owned_owners` list, so only the `github_user` is owned.
+    /// - Empty `include_patterns` and `exclude_patterns`, the `DEFAULT_PRUNED_DIRECTORIES` as `pruned_directories`
+    ///   and a `find_nested_repositories` flag that is set to `false`.
+    /// - A `skip_hidden_directories` flag that is set to `true`, the `DEFAULT_SYSTEM_DIRECTORIES` as
+    ///   `skipped_system_directories` and the `DEFAULT_ALLOWED_DIRECTORIES` as `allowed_directories`.
     ///
     /// # Example
     ///
@@ -34,27 +190,75 @@ impl Default for ProgramSettings {
     ///
     /// assert_eq!(default_config.github_user, "");
     /// assert_eq!(default_config.github_api_token, None);
-    /// assert_eq!(default_config.readme_template_link, "https://raw.githubusercontent.com/PurpleBooth/a-good-readme-template/main/README.md");
+    /// assert_eq!(default_config.readme_template_link, "");
     /// assert_eq!(default_config.replace_in_readme_phrase, "# Project Title");
     /// ```
     fn default() -> Self {
         Self {
             github_user: String::new(),
             github_api_token: None,
-            readme_template_link: "https://raw.githubusercontent.com/PurpleBooth/a-good-readme-template/main/README.md".to_string(),
+            readme_template_link: String::new(),
             replace_in_readme_phrase: "# Project Title".to_string(),
+            readme_template_path: None,
+            community_templates_directory: None,
+            license_link_style: LicenseLinkStyle::External,
+            license_badge: false,
+            license_badge_url: default_license_badge_url(),
+            custom_licenses_directory: None,
+            license_sources: default_license_sources(),
+            api_base_url: default_api_base_url(),
+            api_version: default_api_version(),
+            ca_bundle_path: None,
+            http_proxy: None,
+            https_proxy: None,
+            no_proxy: None,
+            update_manifests: default_update_manifests(),
+            commit_message_template: default_commit_message_template(),
+            community_commit_message: default_community_commit_message(),
+            commit_author: None,
+            branch_name_template: default_branch_name_template(),
+            unclean_repository_policy: UncleanRepositoryPolicy::Skip,
+            owned_owners: vec![],
+            include_patterns: vec![],
+            exclude_patterns: vec![],
+            pruned_directories: default_pruned_directories(),
+            find_nested_repositories: false,
+            skip_hidden_directories: default_skip_hidden_directories(),
+            skipped_system_directories: default_skipped_system_directories(),
+            allowed_directories: default_allowed_directories(),
+            commit_changes: false,
+            commit_signoff: false,
+            commit_on_branch: false,
+            include_foreign: false,
+            patch_directory: None,
+            preselected_license: None,
+            preselected_exception: None,
+            refresh_licenses: false,
+            rescan: false,
         }
     }
 }
 
 impl Display for ProgramSettings {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "\nGithub Username: {}\nGithub API Token: {:?}\nLink to license-template: {}\nGetting replaced in Readme: {}", self.github_user, self.github_api_token, self.readme_template_link, self.replace_in_readme_phrase)
+        writeln!(f, "\nGithub Username: {}\nGithub API Token: {:?}\nLink to license-template: {}\nGetting replaced in Readme: {}\nLocal readme template: {:?}\nCommunity templates: {:?}\nLicense link style: {:?}\nLicense badge: {} ({})\nCustom licenses directory: {:?}\nLicense sources: {:?}\nAPI base URL: {}\nCA bundle: {:?}\nHTTP proxy: {:?}\nHTTPS proxy: {:?}\nUpdate manifests: {}\nCommit message: {}\nCommunity commit message: {}\nCommit author: {:?}\nBranch name: {}\nUnclean repositories: {:?}\nOwned users and organizations: {:?}\nInclude patterns: {:?}\nExclude patterns: {:?}\nPruned directories: {:?}\nNested repositories: {}\nSkip hidden directories: {}\nSystem directories: {:?}\nAllowed directories: {:?}", self.github_user, self.github_api_token, self.readme_template_link, self.replace_in_readme_phrase, self.readme_template_path, self.community_templates_directory, self.license_link_style, self.license_badge, self.license_badge_url, self.custom_licenses_directory, self.license_sources, self.api_base_url, self.ca_bundle_path, self.http_proxy, self.https_proxy, self.update_manifests, self.commit_message_template, self.community_commit_message, self.commit_author, self.branch_name_template, self.unclean_repository_policy, self.owners(), self.include_patterns, self.exclude_patterns, self.pruned_directories, self.find_nested_repositories, self.skip_hidden_directories, self.skipped_system_directories, self.allowed_directories)
     }
 }
 
 impl ProgramSettings {
 
+    /// Returns the users and organizations whose repositories may be licensed.
+    ///
+    /// # Returns
+    ///
+    /// The `owned_owners`, or the `github_user` if none are configured.
+    pub fn owners(&self) -> Vec<String> {
+        if !self.owned_owners.is_empty() {
+            return self.owned_owners.clone();
+        }
+        [self.github_user.clone()].into_iter().filter(|user| !user.is_empty()).collect()
+    }
+
     /// Returns the file path for the settings file.
     ///
     /// This function retrieves the current directory using `std::env::current_dir()`
@@ -78,11 +282,24 @@ impl ProgramSettings {
     /// let file_path = get_settings_file_path();
     /// ```
     fn get_settings_file_path() -> String {
+        ProgramSettings::get_data_file_path("settings.json")
+    }
+
+    /// Returns the path for a file that is stored next to the settings file.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_name` - The name of the file.
+    ///
+    /// # Returns
+    ///
+    /// The file path as a `String`.
+    pub fn get_data_file_path(file_name: &str) -> String {
         format!(
             "{}{}{}",
             std::env::current_dir().unwrap().display(),
             MAIN_SEPARATOR,
-            "settings.json"
+            file_name
         )
     }
 
@@ -196,3 +413,39 @@ impl ProgramSettings {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The entries every settings file has, followed by `extra`.
+    fn settings_json(extra: &str) -> String {
+        format!(
+            "{{\"github_user\": \"\", \"github_api_token\": null, \"readme_template_link\": \"\", \"replace_in_readme_phrase\": \"# Project Title\"{}}}",
+            extra
+        )
+    }
+
+    #[test]
+    fn reads_the_license_source_of_older_settings_files() {
+        let settings = serde_json::from_str::<ProgramSettings>(&settings_json(
+            ", \"license_source\": { \"type\": \"spdx_license_list\", \"path\": \"/opt/license-list-data\" }",
+        ))
+        .unwrap();
+        assert_eq!(
+            settings.license_sources,
+            vec![LicenseSource::SpdxLicenseList { path: "/opt/license-list-data".to_string(), only: vec![] }]
+        );
+    }
+
+    #[test]
+    fn reads_the_license_sources_list() {
+        let settings = serde_json::from_str::<ProgramSettings>(&settings_json(
+            ", \"license_sources\": [{ \"type\": \"embedded\" }, { \"type\": \"github_api\" }]",
+        ))
+        .unwrap();
+        assert_eq!(settings.license_sources, vec![LicenseSource::Embedded, LicenseSource::GithubApi]);
+        let defaults = serde_json::from_str::<ProgramSettings>(&settings_json("")).unwrap();
+        assert_eq!(defaults.license_sources, vec![LicenseSource::GithubApi]);
+    }
+}
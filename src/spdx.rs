@@ -0,0 +1,152 @@
+use crate::license_expression::LicenseExpression;
+
+// The ids of the SPDX license list (https://spdx.org/licenses/) that are known without a
+// download, with their canonical case. Ids of the form "LicenseRef-<idstring>" (and
+// "DocumentRef-<idstring>:LicenseRef-<idstring>") are always valid, for licenses the list does not know.
+
+/// Known SPDX license ids, in their canonical case.
+static LICENSE_IDS: [&str; 112] = [
+    "0BSD", "AAL", "AFL-3.0", "AGPL-1.0-only", "AGPL-1.0-or-later", "AGPL-3.0-only", "AGPL-3.0-or-later",
+    "Apache-1.0", "Apache-1.1", "Apache-2.0", "APSL-2.0", "Artistic-1.0", "Artistic-2.0", "BlueOak-1.0.0",
+    "BSD-1-Clause", "BSD-2-Clause", "BSD-2-Clause-Patent", "BSD-3-Clause", "BSD-3-Clause-Clear",
+    "BSD-3-Clause-LBNL", "BSD-4-Clause", "BSL-1.0", "BUSL-1.1", "CAL-1.0", "CC-BY-1.0", "CC-BY-2.0",
+    "CC-BY-3.0", "CC-BY-4.0", "CC-BY-NC-4.0", "CC-BY-NC-ND-4.0", "CC-BY-NC-SA-4.0", "CC-BY-ND-4.0",
+    "CC-BY-SA-3.0", "CC-BY-SA-4.0", "CC0-1.0", "CDDL-1.0", "CDDL-1.1", "CDLA-Permissive-2.0", "CECILL-2.1",
+    "CECILL-B", "CECILL-C", "CPAL-1.0", "CPL-1.0", "ECL-2.0", "EFL-2.0", "Elastic-2.0", "EPL-1.0",
+    "EPL-2.0", "EUPL-1.1", "EUPL-1.2", "FSFAP", "FTL", "GFDL-1.1-only", "GFDL-1.1-or-later",
+    "GFDL-1.2-only", "GFDL-1.2-or-later", "GFDL-1.3-only", "GFDL-1.3-or-later", "GPL-1.0-only",
+    "GPL-1.0-or-later", "GPL-2.0-only", "GPL-2.0-or-later", "GPL-3.0-only", "GPL-3.0-or-later", "HPND",
+    "ICU", "IJG", "IPL-1.0", "ISC", "LGPL-2.0-only", "LGPL-2.0-or-later", "LGPL-2.1-only",
+    "LGPL-2.1-or-later", "LGPL-3.0-only", "LGPL-3.0-or-later", "LiLiQ-P-1.1", "LPPL-1.3c", "MirOS",
+    "MIT", "MIT-0", "MIT-CMU", "MPL-1.1", "MPL-2.0", "MPL-2.0-no-copyleft-exception", "MS-PL", "MS-RL",
+    "MulanPSL-2.0", "NCSA", "ODbL-1.0", "OFL-1.1", "OpenSSL", "OSL-3.0", "PHP-3.01", "PolyForm-Noncommercial-1.0.0",
+    "PolyForm-Small-Business-1.0.0", "PostgreSQL", "PSF-2.0", "Python-2.0", "Ruby", "SMLNJ", "SSPL-1.0",
+    "Unicode-3.0", "Unicode-DFS-2016", "Unlicense", "UPL-1.0", "Vim", "W3C", "WTFPL", "X11", "Zlib",
+    "zlib-acknowledgement", "ZPL-2.1",
+];
+
+/// Known SPDX exception ids, in their canonical case.
+static EXCEPTION_IDS: [&str; 23] = [
+    "Autoconf-exception-2.0", "Autoconf-exception-3.0", "Bison-exception-2.2", "Classpath-exception-2.0",
+    "eCos-exception-2.0", "FLTK-exception", "Font-exception-2.0", "GCC-exception-2.0", "GCC-exception-3.1",
+    "GPL-3.0-linking-exception", "LGPL-3.0-linking-exception", "Libtool-exception", "Linux-syscall-note",
+    "LLVM-exception", "OCaml-LGPL-linking-exception", "OpenJDK-assembly-exception-1.0",
+    "openvpn-openssl-exception", "Qt-GPL-exception-1.0", "Qt-LGPL-exception-1.1", "Swift-exception",
+    "u-boot-exception-2.0", "Universal-FOSS-exception-1.0", "WxWindows-exception-3.1",
+];
+
+/// Deprecated SPDX license ids and the expressions that replace them.
+static DEPRECATED_IDS: [(&str, &str); 29] = [
+    ("AGPL-1.0", "AGPL-1.0-only"),
+    ("AGPL-3.0", "AGPL-3.0-only"),
+    ("BSD-2-Clause-FreeBSD", "BSD-2-Clause"),
+    ("BSD-2-Clause-NetBSD", "BSD-2-Clause"),
+    ("GFDL-1.1", "GFDL-1.1-only"),
+    ("GFDL-1.2", "GFDL-1.2-only"),
+    ("GFDL-1.3", "GFDL-1.3-only"),
+    ("GPL-1.0", "GPL-1.0-only"),
+    ("GPL-1.0+", "GPL-1.0-or-later"),
+    ("GPL-2.0", "GPL-2.0-only"),
+    ("GPL-2.0+", "GPL-2.0-or-later"),
+    ("GPL-2.0-with-autoconf-exception", "GPL-2.0-only WITH Autoconf-exception-2.0"),
+    ("GPL-2.0-with-bison-exception", "GPL-2.0-or-later WITH Bison-exception-2.2"),
+    ("GPL-2.0-with-classpath-exception", "GPL-2.0-only WITH Classpath-exception-2.0"),
+    ("GPL-2.0-with-font-exception", "GPL-2.0-only WITH Font-exception-2.0"),
+    ("GPL-2.0-with-GCC-exception", "GPL-2.0-or-later WITH GCC-exception-2.0"),
+    ("GPL-3.0", "GPL-3.0-only"),
+    ("GPL-3.0+", "GPL-3.0-or-later"),
+    ("GPL-3.0-with-autoconf-exception", "GPL-3.0-only WITH Autoconf-exception-3.0"),
+    ("GPL-3.0-with-GCC-exception", "GPL-3.0-only WITH GCC-exception-3.1"),
+    ("LGPL-2.0", "LGPL-2.0-only"),
+    ("LGPL-2.0+", "LGPL-2.0-or-later"),
+    ("LGPL-2.1", "LGPL-2.1-only"),
+    ("LGPL-2.1+", "LGPL-2.1-or-later"),
+    ("LGPL-3.0", "LGPL-3.0-only"),
+    ("LGPL-3.0+", "LGPL-3.0-or-later"),
+    ("Nunit", "zlib-acknowledgement"),
+    ("StandardML-NJ", "SMLNJ"),
+    ("wxWindows", "LGPL-2.0-or-later WITH WxWindows-exception-3.1"),
+];
+
+/// Returns the canonical case of a known license id.
+///
+/// # Arguments
+///
+/// * `id` - The license id in any case, like `mit`.
+pub fn canonical_license_id(id: &str) -> Option<&'static str> {
+    LICENSE_IDS.iter().find(|known| known.eq_ignore_ascii_case(id)).copied()
+}
+
+/// Returns the canonical case of a known exception id.
+///
+/// # Arguments
+///
+/// * `id` - The exception id in any case, like `classpath-exception-2.0`.
+pub fn canonical_exception_id(id: &str) -> Option<&'static str> {
+    EXCEPTION_IDS.iter().find(|known| known.eq_ignore_ascii_case(id)).copied()
+}
+
+/// Returns the expression that replaces a deprecated license id.
+///
+/// # Arguments
+///
+/// * `id` - The license id in any case, like `GPL-2.0+`.
+pub fn deprecated_replacement(id: &str) -> Option<&'static str> {
+    DEPRECATED_IDS
+        .iter()
+        .find(|(deprecated, _)| deprecated.eq_ignore_ascii_case(id))
+        .map(|(_, replacement)| *replacement)
+}
+
+/// Checks whether an id only has the characters SPDX allows in ids: letters, digits, `-` and `.`.
+///
+/// # Arguments
+///
+/// * `id` - The license or exception id, without the `+` suffix.
+pub fn is_idstring(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+}
+
+/// Checks whether an id is a user defined reference, like `LicenseRef-ACME` or
+/// `DocumentRef-spdx-tool-1.2:LicenseRef-MIT-Style-2`.
+///
+/// # Arguments
+///
+/// * `id` - The license id.
+pub fn is_license_ref(id: &str) -> bool {
+    let license_ref = match id.split_once(':') {
+        Some((document_ref, license_ref)) => {
+            match document_ref.strip_prefix("DocumentRef-") {
+                Some(document) if is_idstring(document) => license_ref,
+                _ => return false,
+            }
+        }
+        None => id,
+    };
+    license_ref.strip_prefix("LicenseRef-").is_some_and(is_idstring)
+}
+
+/// Checks whether two SPDX ids name the same license, ignoring the case and deprecated ids.
+///
+/// For example `GPL-2.0` (as used by the GitHub API) and `gpl-2.0-only` are the same license.
+///
+/// # Arguments
+///
+/// * `first` - The first id.
+/// * `second` - The second id.
+pub fn same_license(first: &str, second: &str) -> bool {
+    if first.eq_ignore_ascii_case(second) {
+        return true;
+    }
+    // Single ids that are not deprecated only name the same license in the same spelling,
+    // which spares parsing them when long license lists are merged.
+    let is_single = |id: &str| !id.contains(|c: char| c.is_whitespace() || c == '(' || c == ')');
+    if is_single(first) && is_single(second) && deprecated_replacement(first).is_none() && deprecated_replacement(second).is_none() {
+        return false;
+    }
+    let normalized = |id: &str| LicenseExpression::parse(id).and_then(|expression| expression.normalize()).ok();
+    match (normalized(first), normalized(second)) {
+        (Some(first), Some(second)) => first == second,
+        _ => false,
+    }
+}
@@ -0,0 +1,201 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::github_license::GithubLicense;
+
+// Loader for the SPDX license-list-data (https://github.com/spdx/license-list-data).
+// Either a checkout of the repository or one of its JSON exports can be used, so
+// licenses that the GitHub API does not know (BSD-2-Clause-Patent, EUPL-1.2, ...) can be chosen.
+
+static SPDX_HTML_URL: &str = "https://spdx.org/licenses";
+
+/// One entry of the `licenses` array in `json/licenses.json`.
+///
+/// The detail files in `json/details/<id>.json` share the same field names,
+/// additionally carrying the license text and the standard template.
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SpdxLicenseEntry {
+    license_id: String,
+    name: String,
+    #[serde(default)]
+    reference: String,
+    #[serde(default)]
+    details_url: String,
+    #[serde(default)]
+    is_deprecated_license_id: bool,
+    #[serde(default)]
+    is_osi_approved: bool,
+    #[serde(default)]
+    license_text: Option<String>,
+    #[serde(default)]
+    standard_license_template: Option<String>,
+}
+
+/// The top level object of `json/licenses.json`.
+#[derive(Deserialize)]
+struct SpdxLicenseList {
+    licenses: Vec<SpdxLicenseEntry>,
+}
+
+/// Renders a SPDX standard license template to plain text.
+///
+/// Variable blocks (`<<var;name=...;original="...";match=...>>`) are replaced with their
+/// original text, optional markers (`<<beginOptional>>`, `<<endOptional>>`) are dropped.
+///
+/// # Arguments
+///
+/// * `template` - The `standardLicenseTemplate` of a license.
+///
+/// # Returns
+///
+/// The rendered license text.
+fn render_template(template: &str) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("<<") {
+        rendered.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find(">>") else {
+            rest = &rest[start..];
+            break;
+        };
+        let tag = &rest[start + 2..start + end];
+        if tag.starts_with("var;") {
+            if let Some(original) = tag
+                .split(';')
+                .find_map(|part| part.strip_prefix("original=\""))
+            {
+                rendered.push_str(original.trim_end_matches('"'));
+            }
+        }
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Replaces the common SPDX copyright placeholders with the ones `GithubLicense::set_username_and_year` knows.
+///
+/// # Arguments
+///
+/// * `text` - The license text.
+///
+/// # Returns
+///
+/// The license text with `[year]` and `[fullname]` placeholders.
+fn normalize_placeholders(text: &str) -> String {
+    text.replace("<year>", "[year]")
+        .replace("<owner>", "[fullname]")
+        .replace("<copyright holders>", "[fullname]")
+        .replace("<name of author>", "[fullname]")
+}
+
+/// Maps a SPDX license entry into a `GithubLicense`.
+///
+/// # Arguments
+///
+/// * `entry` - The SPDX license entry, with or without text.
+/// * `text` - The license text, if it was read from a separate file.
+fn into_github_license(entry: SpdxLicenseEntry, text: String) -> GithubLicense {
+    GithubLicense {
+        key: entry.license_id.to_lowercase(),
+        name: entry.name,
+        spdx_id: entry.license_id.clone(),
+        url: entry.details_url,
+        node_id: String::new(),
+        html_url: if entry.reference.is_empty() {
+            format!("{}/{}.html", SPDX_HTML_URL, entry.license_id)
+        } else {
+            entry.reference
+        },
+        description: if entry.is_osi_approved {
+            "OSI approved license from the SPDX license list".to_string()
+        } else {
+            "License from the SPDX license list".to_string()
+        },
+        implementation: String::new(),
+        permissions: vec![],
+        conditions: vec![],
+        limitations: vec![],
+        body: normalize_placeholders(&text),
+        featured: false,
+    }
+}
+
+/// Looks up the text of a license whose entry does not carry it.
+///
+/// Tries `details/<id>.json` next to the list file, then `../text/<id>.txt`.
+///
+/// # Arguments
+///
+/// * `json_dir` - The directory of the `licenses.json` file.
+/// * `license_id` - The SPDX id of the license.
+async fn read_license_text(json_dir: &Path, license_id: &str) -> Option<String> {
+    let details_path = json_dir.join("details").join(format!("{}.json", license_id));
+    if let Ok(content) = tokio::fs::read_to_string(&details_path).await {
+        if let Ok(details) = serde_json::from_str::<SpdxLicenseEntry>(&content) {
+            if let Some(template) = details.standard_license_template.filter(|t| !t.is_empty()) {
+                return Some(render_template(&template));
+            }
+            if details.license_text.is_some() {
+                return details.license_text;
+            }
+        }
+    }
+    let text_path = json_dir.join("..").join("text").join(format!("{}.txt", license_id));
+    tokio::fs::read_to_string(text_path).await.ok()
+}
+
+/// Loads licenses from a checkout or JSON export of the SPDX license-list-data.
+///
+/// # Arguments
+///
+/// * `path` - Either the root of a license-list-data checkout, or a `licenses.json` file.
+///   The file may also be a JSON array of license detail objects.
+/// * `only` - SPDX ids to load. If empty, all non-deprecated licenses are loaded.
+///
+/// # Returns
+///
+/// A vector of `GithubLicense` objects, sorted by SPDX id.
+///
+/// # Errors
+///
+/// Returns an error if the list file cannot be read or parsed.
+/// Licenses without any available text are skipped.
+pub async fn load_spdx_licenses(path: &str, only: &[String]) -> Result<Vec<GithubLicense>, Box<dyn Error>> {
+    let list_path: PathBuf = if Path::new(path).is_dir() {
+        Path::new(path).join("json").join("licenses.json")
+    } else {
+        PathBuf::from(path)
+    };
+    let json_dir = list_path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let content = tokio::fs::read_to_string(&list_path).await?;
+    let entries = match serde_json::from_str::<SpdxLicenseList>(&content) {
+        Ok(list) => list.licenses,
+        Err(_) => serde_json::from_str::<Vec<SpdxLicenseEntry>>(&content)?,
+    };
+
+    let mut licenses: Vec<GithubLicense> = vec![];
+    for entry in entries {
+        let wanted = if only.is_empty() {
+            !entry.is_deprecated_license_id
+        } else {
+            only.iter().any(|id| id.eq_ignore_ascii_case(&entry.license_id))
+        };
+        if !wanted {
+            continue;
+        }
+        let text = match (&entry.standard_license_template, &entry.license_text) {
+            (Some(template), _) if !template.is_empty() => Some(render_template(template)),
+            (_, Some(text)) => Some(text.clone()),
+            _ => read_license_text(&json_dir, &entry.license_id).await,
+        };
+        if let Some(text) = text {
+            licenses.push(into_github_license(entry, text));
+        }
+    }
+    licenses.sort_by(|a, b| a.spdx_id.cmp(&b.spdx_id));
+    Ok(licenses)
+}
@@ -0,0 +1,368 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings_file::ProgramSettings;
+
+// Decides which directories the walker steps into and which repositories it takes.
+// Patterns work like in a .gitignore: `*` and `?` stay within a path component, `**` spans components,
+// and a pattern without a `/` matches the name of a directory anywhere, like `node_modules`.
+//
+// The filter policy: hidden directories (starting with a dot) and system directories are skipped,
+// unless they are on the allow-list, so dotfiles repositories like `~/.config/nvim` can still be found.
+
+/// Directories that are skipped by default, as they hold dependencies, caches or build output with lots of files.
+pub static DEFAULT_PRUNED_DIRECTORIES: [&str; 16] = [
+    "node_modules",
+    "bower_components",
+    "target",
+    "build",
+    "dist",
+    ".venv",
+    "venv",
+    "__pycache__",
+    ".tox",
+    ".gradle",
+    ".m2",
+    ".npm",
+    ".cargo",
+    ".rustup",
+    ".cache",
+    "Pods",
+];
+
+/// System directories that never contain repositories worth licensing, on Windows, Linux and macOS.
+pub static DEFAULT_SYSTEM_DIRECTORIES: [&str; 15] = [
+    "$*",
+    "System Volume Information",
+    "AppData",
+    "lost+found",
+    "*:/Windows",
+    "*:/Program Files",
+    "*:/Program Files (x86)",
+    "*:/ProgramData",
+    "/proc",
+    "/sys",
+    "/dev",
+    "/run",
+    "/snap",
+    "/System",
+    "/private",
+];
+
+/// Hidden directories that are searched by default, as they often hold dotfiles repositories.
+pub static DEFAULT_ALLOWED_DIRECTORIES: [&str; 2] = ["~/.config/*", "~/.dotfiles"];
+
+/// A glob pattern for paths.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GlobPattern {
+    /// The pattern with `/` as separator.
+    pattern: String,
+    /// Whether the pattern only matches the name of a directory, as it has no `/`.
+    name_only: bool,
+}
+
+impl GlobPattern {
+    /// Parses a pattern, a leading `~/` is the home directory and a trailing `/` is ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - The pattern, like `node_modules`, `**/archive/**` or `~/work/*`.
+    pub fn parse(pattern: &str) -> Self {
+        let mut pattern = normalize(pattern.trim());
+        if let Some(rest) = pattern.strip_prefix("~/") {
+            if let Ok(home) = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")) {
+                pattern = format!("{}/{}", normalize(&home).trim_end_matches('/'), rest);
+            }
+        }
+        let pattern = pattern.trim_end_matches('/').to_string();
+        Self { name_only: !pattern.contains('/'), pattern }
+    }
+
+    /// Checks whether a path matches the pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of a directory.
+    pub fn matches(&self, path: &Path) -> bool {
+        let path = normalize(&path.display().to_string());
+        let path = path.trim_end_matches('/');
+        let subject = if self.name_only { path.rsplit('/').next().unwrap_or(path) } else { path };
+        glob_match(&chars(&self.pattern), &chars(subject))
+    }
+
+    /// Checks whether a directory lies on the way to the paths the pattern matches,
+    /// like `/home/me` for `/home/me/.config/*`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of a directory.
+    pub fn leads_to(&self, path: &Path) -> bool {
+        if self.name_only {
+            return false;
+        }
+        let literal_end = self.pattern.find(['*', '?']).unwrap_or(self.pattern.len());
+        let base = match self.pattern[..literal_end].rfind('/') {
+            Some(slash) if literal_end < self.pattern.len() => &self.pattern[..=slash],
+            _ => &self.pattern[..literal_end],
+        };
+        let directory = format!("{}/", normalize(&path.display().to_string()).trim_end_matches('/'));
+        chars(base).starts_with(&chars(&directory))
+    }
+}
+
+/// Replaces the windows path separator with `/`.
+///
+/// # Arguments
+///
+/// * `path` - The path.
+fn normalize(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+/// Returns the characters to match, windows paths are matched case-insensitively.
+///
+/// # Arguments
+///
+/// * `text` - The pattern or path.
+fn chars(text: &str) -> Vec<char> {
+    if cfg!(windows) {
+        text.to_lowercase().chars().collect()
+    } else {
+        text.chars().collect()
+    }
+}
+
+/// Matches a path against a glob pattern.
+///
+/// # Arguments
+///
+/// * `pattern` - The characters of the pattern.
+/// * `path` - The characters of the path.
+fn glob_match(pattern: &[char], path: &[char]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        ['*', '*', rest @ ..] => {
+            // "**/" also matches no directory at all.
+            if let ['/', after_slash @ ..] = rest {
+                if glob_match(after_slash, path) {
+                    return true;
+                }
+            }
+            (0..=path.len()).any(|skipped| glob_match(rest, &path[skipped..]))
+        }
+        ['*', rest @ ..] => (0..=path.len())
+            .take_while(|skipped| path[..*skipped].iter().all(|c| *c != '/'))
+            .any(|skipped| glob_match(rest, &path[skipped..])),
+        ['?', rest @ ..] => matches!(path, [c, ..] if *c != '/') && glob_match(rest, &path[1..]),
+        [literal, rest @ ..] => path.first() == Some(literal) && glob_match(rest, &path[1..]),
+    }
+}
+
+/// Which directories the walker steps into, and which repositories it takes.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WalkFilter {
+    /// Only repositories matching one of these patterns are taken, all if it is empty.
+    include: Vec<GlobPattern>,
+    /// Directories matching these patterns are skipped, with everything in them.
+    exclude: Vec<GlobPattern>,
+    /// Directories that are skipped, unless they are a repository.
+    pruned: Vec<GlobPattern>,
+    /// Whether the walker looks for repositories inside of other repositories.
+    pub(crate) nested_repositories: bool,
+    /// Whether directories starting with a dot are skipped.
+    skip_hidden: bool,
+    /// System directories that are skipped.
+    system: Vec<GlobPattern>,
+    /// Directories that are searched, even if they are hidden, system or pruned directories.
+    allowed: Vec<GlobPattern>,
+}
+
+impl WalkFilter {
+    /// Creates the filter from the patterns and the filter policy of the settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The program settings.
+    pub fn from_settings(settings: &ProgramSettings) -> Self {
+        let parse = |patterns: &[String]| patterns.iter().map(|pattern| GlobPattern::parse(pattern)).collect();
+        Self {
+            include: parse(&settings.include_patterns),
+            exclude: parse(&settings.exclude_patterns),
+            pruned: parse(&settings.pruned_directories),
+            nested_repositories: settings.find_nested_repositories,
+            skip_hidden: settings.skip_hidden_directories,
+            system: parse(&settings.skipped_system_directories),
+            allowed: parse(&settings.allowed_directories),
+        }
+    }
+
+    /// Checks whether a directory is on the allow-list, or on the way to a directory on it.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the directory.
+    pub fn allows(&self, path: &Path) -> bool {
+        self.allowed.iter().any(|pattern| pattern.matches(path) || pattern.leads_to(path))
+    }
+
+    /// Checks whether a directory is skipped as a hidden or system directory, following the filter policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the directory.
+    pub fn skips(&self, path: &Path) -> bool {
+        let is_hidden = self.skip_hidden
+            && path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        let is_system = self.system.iter().any(|pattern| pattern.matches(path));
+        (is_hidden || is_system) && !self.allows(path)
+    }
+
+    /// Checks whether a directory and everything in it is excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the directory.
+    pub fn excludes(&self, path: &Path) -> bool {
+        self.exclude.iter().any(|pattern| pattern.matches(path))
+    }
+
+    /// Checks whether a directory, that is no repository, is not worth looking into.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the directory.
+    pub fn prunes(&self, path: &Path) -> bool {
+        self.pruned.iter().any(|pattern| pattern.matches(path)) && !self.allows(path)
+    }
+
+    /// Checks whether a repository is taken, as it matches the include patterns.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the repository.
+    pub fn includes(&self, path: &Path) -> bool {
+        self.include.is_empty() || self.include.iter().any(|pattern| pattern.matches(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+
+    /// The filter with the default policy of the settings.
+    fn default_filter() -> WalkFilter {
+        WalkFilter::from_settings(&ProgramSettings::default())
+    }
+
+    /// The home directory the `~/` patterns expand to.
+    fn home() -> PathBuf {
+        PathBuf::from(std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")).unwrap())
+    }
+
+    #[test]
+    fn allows_dotfiles_repositories_but_skips_other_hidden_directories() {
+        let filter = default_filter();
+        assert!(!filter.skips(&home().join(".config")));
+        assert!(!filter.skips(&home().join(".config").join("nvim")));
+        assert!(!filter.skips(&home().join(".dotfiles")));
+        assert!(filter.skips(&home().join(".cache")));
+        assert!(filter.skips(&home().join(".local")));
+    }
+
+    #[test]
+    fn skips_system_directories() {
+        let filter = default_filter();
+        assert!(filter.skips(Path::new("C:/$Recycle.Bin")));
+        assert!(filter.skips(Path::new("D:/$RECYCLE.BIN")));
+        assert!(filter.skips(Path::new("C:/Windows")));
+        assert!(filter.skips(Path::new("C:/Users/me/AppData")));
+        assert!(filter.skips(Path::new("/proc")));
+        assert!(!filter.skips(Path::new("C:/Users/me/Windows")));
+        assert!(!filter.skips(Path::new("/home/me/proc")));
+    }
+
+    #[test]
+    fn hidden_directories_are_searched_without_the_policy() {
+        let settings = ProgramSettings { skip_hidden_directories: false, ..ProgramSettings::default() };
+        let filter = WalkFilter::from_settings(&settings);
+        assert!(!filter.skips(&home().join(".cache")));
+        assert!(filter.skips(Path::new("C:/$Recycle.Bin")));
+    }
+
+    #[test]
+    fn dollar_and_dot_only_matter_at_the_start_of_a_name() {
+        // The old heuristics skipped every path with a '$' or a "/." anywhere in it.
+        let filter = default_filter();
+        assert!(!filter.skips(Path::new("/home/me/price$calculator")));
+        assert!(!filter.skips(Path::new("/home/me/projects/site.io")));
+        assert!(filter.skips(Path::new("/home/me/projects/.idea")));
+        assert!(filter.skips(Path::new("/home/me/$tmp")));
+    }
+
+    #[test]
+    fn star_stays_within_a_component() {
+        let pattern = GlobPattern::parse("/home/*/work");
+        assert!(pattern.matches(Path::new("/home/me/work")));
+        assert!(!pattern.matches(Path::new("/home/me/projects/work")));
+        assert!(GlobPattern::parse("/srv/repo-?").matches(Path::new("/srv/repo-1")));
+        assert!(!GlobPattern::parse("/srv/repo-?").matches(Path::new("/srv/repo-10")));
+    }
+
+    #[test]
+    fn double_star_spans_components() {
+        let pattern = GlobPattern::parse("**/archive/**");
+        assert!(pattern.matches(Path::new("/home/me/archive/old")));
+        assert!(pattern.matches(Path::new("/archive/old/deeper")));
+        assert!(!pattern.matches(Path::new("/home/me/archived/old")));
+        assert!(GlobPattern::parse("/home/**/site").matches(Path::new("/home/site")));
+    }
+
+    #[test]
+    fn patterns_without_a_slash_match_names() {
+        let pattern = GlobPattern::parse("node_modules");
+        assert!(pattern.matches(Path::new("/home/me/web/node_modules")));
+        assert!(!pattern.matches(Path::new("/home/me/web/node_modules_backup")));
+        assert!(GlobPattern::parse("*.git").matches(Path::new("/srv/project.git")));
+    }
+
+    #[test]
+    fn leads_to_the_ancestors_of_allowed_paths() {
+        let pattern = GlobPattern::parse("/home/me/.config/*");
+        assert!(pattern.leads_to(Path::new("/home/me")));
+        assert!(pattern.leads_to(Path::new("/home/me/.config")));
+        assert!(!pattern.leads_to(Path::new("/home/me/.cache")));
+        assert!(!pattern.leads_to(Path::new("/home/other")));
+        assert!(!GlobPattern::parse("nvim").leads_to(Path::new("/home/me")));
+    }
+
+    #[test]
+    fn pruned_directories_are_pruned_unless_allowed() {
+        let mut settings = ProgramSettings::default();
+        settings.allowed_directories.push("/srv/build".to_string());
+        let filter = WalkFilter::from_settings(&settings);
+        assert!(filter.prunes(Path::new("/home/me/web/node_modules")));
+        assert!(filter.prunes(Path::new("/home/me/app/build")));
+        assert!(!filter.prunes(Path::new("/srv/build")));
+        assert!(!filter.prunes(Path::new("/home/me/app/src")));
+    }
+
+    #[test]
+    fn include_and_exclude_patterns() {
+        let settings = ProgramSettings {
+            include_patterns: vec!["/home/me/work/*".to_string()],
+            exclude_patterns: vec!["**/archive".to_string()],
+            ..ProgramSettings::default()
+        };
+        let filter = WalkFilter::from_settings(&settings);
+        assert!(filter.includes(Path::new("/home/me/work/app")));
+        assert!(!filter.includes(Path::new("/home/me/play/app")));
+        assert!(filter.excludes(Path::new("/home/me/work/archive")));
+        assert!(!filter.excludes(Path::new("/home/me/work/app")));
+        assert!(default_filter().includes(Path::new("/anywhere")));
+    }
+}
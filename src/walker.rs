@@ -1,203 +1,538 @@
-use std::fmt::Display;
-use std::path::MAIN_SEPARATOR;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use futures::executor::block_on;
+use indicatif::ProgressBar;
 use sysinfo::Disks;
-use tokio::task::JoinHandle;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tokio::sync::Semaphore;
+use tokio::task::{JoinHandle, JoinSet};
 use tokio::time::Instant;
 use walkdir::WalkDir;
 
 use crate::git_dir::GitDir;
 use crate::github_license::GithubLicense;
+use crate::output_printer::PrintMode;
+use crate::repository_index::RepositoryIndex;
+use crate::repository_kind::{attribute_repositories, is_bare_repository};
+use crate::walk_filter::WalkFilter;
+
+// The search runs in two stages: a pool of blocking threads walks the directory trees and sends the repositories
+// it finds through a channel, while the runtime analyses a limited number of them at the same time.
+
+/// How many repositories are analysed at the same time.
+static ANALYSIS_CONCURRENCY: usize = 16;
+
+/// How many found repositories can wait for their analysis, before the walking threads wait as well.
+static DISCOVERY_BUFFER: usize = 256;
+
+/// The most threads that walk the directory trees.
+static MAX_WALKER_THREADS: usize = 8;
+
+/// How often the spinner shows the progress of the search.
+static PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The most characters of the current path the spinner shows.
+static PROGRESS_PATH_LENGTH: usize = 60;
+
+/// The task that quits the program on Ctrl+C, while no search is running.
+static QUIT_LISTENER: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+
+/// The progress of a running search, shared by the walking threads, the analysis and the spinner.
+#[derive(Debug, Default)]
+pub struct ScanProgress {
+    /// The directories the walkers looked at.
+    directories_visited: AtomicUsize,
+    /// The directories the walkers could not read.
+    unreadable_directories: AtomicUsize,
+    /// The repositories the walkers found.
+    repositories_found: AtomicUsize,
+    /// The directory one of the walkers is looking at.
+    current_path: Mutex<PathBuf>,
+    /// Whether the search is over, because it was stopped or all directories were searched.
+    stopped: AtomicBool,
+}
+
+impl ScanProgress {
+    /// Counts a visited directory and makes it the current path, unless another walker is just setting it.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the directory.
+    fn visit(&self, path: &Path) {
+        self.directories_visited.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut current_path) = self.current_path.try_lock() {
+            current_path.clear();
+            current_path.push(path);
+        }
+    }
+
+    /// Ends the search, the walkers stop and the repositories found so far are used.
+    ///
+    /// # Returns
+    ///
+    /// Whether the search was already over.
+    fn stop(&self) -> bool {
+        self.stopped.swap(true, Ordering::Relaxed)
+    }
+
+    /// Checks whether the search is over.
+    fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// Returns the progress as the message of the spinner, like
+    /// `1234 directories visited, 5 repositories found (Ctrl+C uses them)` and the current path below it.
+    fn message(&self) -> String {
+        let current_path = self
+            .current_path
+            .lock()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        let skipped = current_path.chars().count().saturating_sub(PROGRESS_PATH_LENGTH);
+        let current_path = if skipped > 0 {
+            format!("...{}", current_path.chars().skip(skipped + 3).collect::<String>())
+        } else {
+            current_path
+        };
+        format!(
+            "{} directories visited, {} repositories found (Ctrl+C stops the search and uses them)\nSearching {}",
+            self.directories_visited.load(Ordering::Relaxed),
+            self.repositories_found.load(Ordering::Relaxed),
+            current_path
+        )
+    }
+}
+
+/// What a search did, printed once it is over.
+#[derive(Debug, Clone, Default)]
+pub struct ScanSummary {
+    /// The disks (or rather their mount points) that were searched.
+    pub(crate) roots: usize,
+    /// The directories the walkers looked at.
+    pub(crate) directories_visited: usize,
+    /// The directories the walkers could not read, like those without permission.
+    pub(crate) unreadable_directories: usize,
+    /// The repositories that were found and analysed.
+    pub(crate) repositories_found: usize,
+    /// How long the search took.
+    pub(crate) duration: Duration,
+    /// Whether the search was stopped, before all directories were searched.
+    pub(crate) stopped_early: bool,
+    /// Whether the repositories were taken from the index, instead of searching the disks.
+    pub(crate) from_index: bool,
+    /// The repositories of the index that changed and were read again.
+    pub(crate) revalidated: usize,
+    /// The repositories of the index that do not exist anymore.
+    pub(crate) removed: usize,
+}
+
+impl Display for ScanSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.from_index {
+            writeln!(f, "Reading the repository index took: {:.1}s (--rescan searches all disks again)", self.duration.as_secs_f32())?;
+            writeln!(f, "  Changed repositories:  {}", self.revalidated)?;
+            writeln!(f, "  Removed repositories:  {}", self.removed)?;
+            return write!(f, "  Repositories found:    {}", self.repositories_found);
+        }
+        writeln!(
+            f,
+            "Searching took: {:.1}s{}",
+            self.duration.as_secs_f32(),
+            if self.stopped_early { " (stopped early, not all directories were searched)" } else { "" }
+        )?;
+        writeln!(f, "  Disks searched:        {}", self.roots)?;
+        writeln!(f, "  Directories visited:   {}", self.directories_visited)?;
+        if self.unreadable_directories > 0 {
+            writeln!(f, "  Unreadable directories: {}", self.unreadable_directories)?;
+        }
+        write!(f, "  Repositories found:    {}", self.repositories_found)
+    }
+}
 
 /// This is an asynchronous function named `init_search` that initializes the
 /// searching for git directories in all of the system's disks.
 ///
 /// # Arguments
 ///
-/// * `time` - An instance of `Instant` used to measure the elapsed time taken
-///   by the search process.
 /// * `licenses` - A vector of GithubLicense. These licenses constraints in
 ///   finding the git directories.
+/// * `filter` - The `WalkFilter` that decides which directories are searched.
+/// * `fingerprint` - The fingerprint of the licenses and settings, that is stored in the index.
+/// * `progress_bar` - The spinner that shows the progress of the search.
+/// * `print_mode` - The print mode, for errors while writing the index.
 ///
 /// # Return
 ///
 /// This function returns a Vector of `GitDir` containing the git directories
-/// found in the search process.
+/// found in the search process, and the summary of the search.
 ///
 /// # Behavior
 ///
-/// The function creates a new `System` instance, getting all system's info
-/// at once. It then iterates over all the system's disks and for each disk,
-/// it spawns a new Tokio task that starts the search operation on that disk.
+/// The function collects the mount points of all system's disks, leaving out
+/// those that lie inside of another one, and walks them on a pool of blocking
+/// threads (see `start_walking`).
+///
+/// While the search runs, the spinner shows the visited directories, the found
+/// repositories and the current path. Pressing Ctrl+C stops the walkers, and the
+/// repositories found so far are used. After the search, Ctrl+C quits the program as usual.
 ///
-/// These tasks are all stored in a `task_holder` Vector, and the function
-/// waits for all these tasks to complete using futures `join_all` function.
-/// Once all tasks have finished execution, it iterates over the completed
-/// tasks, checks if they're `Ok` and if so, adds the results into the
-/// `dirs` Vector of the git directories. If the task's result is an error,
-/// it is ignored.
+/// Every repository the walkers send through the channel is analysed right away
+/// (see `analyse_repositories`). Once the walkers are done, the function waits
+/// for the remaining analyses and gathers their results.
 ///
-/// After all results have been gathered, the function prints out the
-/// searching duration in seconds, and eventually returns the `dirs` Vector.
+/// After all results have been gathered, a complete search is written into the
+/// `RepositoryIndex` for the next run, and the function returns the `dirs` Vector
+/// with a `ScanSummary` of the search.
 ///
 /// # Asynchronous nature
 ///
 /// Note that since this is an asynchronous function using async/await
 /// syntax, it should be run in an async context.
 pub async fn init_search(
-    time: Instant,
     licenses: Vec<GithubLicense>,
-) -> Vec<GitDir> {
+    filter: WalkFilter,
+    fingerprint: u64,
+    progress_bar: &ProgressBar,
+    print_mode: &mut PrintMode,
+) -> (Vec<GitDir>, ScanSummary) {
+    let time = Instant::now();
     let disks = Disks::new_with_refreshed_list();
-    // let system = System::new_all();
-    let mut task_holder: Vec<JoinHandle<Vec<GitDir>>> = vec![];
-    disks.iter().for_each(|disk| {
-        task_holder.push(tokio::spawn(start_walking(
-            disk.mount_point().display().to_string(),
-            licenses.clone(),
-        )))
-    });
-    let mut dirs: Vec<GitDir> = vec![];
-    futures::future::join_all(task_holder)
-        .await
+    let mount_points = disks
         .iter()
-        .filter_map(|dir| {
-            if let Ok(result) = dir {
-                Some(result)
-            } else {
-                None
+        .map(|disk| disk.mount_point().to_path_buf())
+        .collect::<HashSet<PathBuf>>();
+    // A disk mounted at "/home" is walked with "/" already.
+    let roots = mount_points
+        .iter()
+        .filter(|root| !mount_points.iter().any(|other| other != *root && root.starts_with(other)))
+        .cloned()
+        .collect::<Vec<PathBuf>>();
+
+    let roots_count = roots.len();
+
+    // While searching, Ctrl+C stops this search instead of quitting.
+    if let Some(quit_listener) = QUIT_LISTENER.lock().ok().and_then(|mut listener| listener.take()) {
+        quit_listener.abort();
+    }
+    let progress = Arc::new(ScanProgress::default());
+    let stopping = progress.clone();
+    let interrupt = tokio::spawn(async move {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if stopping.stop() {
+                process::exit(130);
             }
-        })
-        .for_each(|dir| {
-            dir.iter().for_each(|git_dir| {
-                dirs.push(git_dir.clone());
-            });
+        }
+    });
+    let (ticking_bar, ticking_progress) = (progress_bar.clone(), progress.clone());
+    let ticker = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PROGRESS_INTERVAL);
+        loop {
+            interval.tick().await;
+            ticking_bar.set_message(ticking_progress.message());
+        }
+    });
+
+    let (sender, receiver) = channel::<String>(DISCOVERY_BUFFER);
+    let walking = tokio::spawn(start_walking(roots, filter.clone(), sender, progress.clone()));
+    let dirs = analyse_repositories(receiver, licenses, &progress).await;
+    let _ = walking.await;
+    ticker.abort();
+    interrupt.abort();
+    progress_bar.set_message("Searching");
+
+    let summary = ScanSummary {
+        roots: roots_count,
+        directories_visited: progress.directories_visited.load(Ordering::Relaxed),
+        unreadable_directories: progress.unreadable_directories.load(Ordering::Relaxed),
+        repositories_found: dirs.len(),
+        duration: time.elapsed(),
+        stopped_early: progress.stop(),
+        ..ScanSummary::default()
+    };
+    quit_on_interrupt();
+    // An incomplete index would hide the repositories that were not found.
+    if !summary.stopped_early {
+        RepositoryIndex::of(filter, fingerprint, &dirs).await.write(print_mode).await;
+    }
+    (attribute_repositories(dirs), summary)
+}
+
+/// Quits the program on Ctrl+C after a search.
+///
+/// Once Ctrl+C is handled by the runtime, it is not handled by the system anymore until the program ends,
+/// so after the search is over, it has to quit the program like it would without the handler.
+fn quit_on_interrupt() {
+    let listener = tokio::spawn(async {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            process::exit(130);
+        }
+    });
+    if let Some(previous) = QUIT_LISTENER.lock().ok().and_then(|mut quit_listener| quit_listener.replace(listener)) {
+        previous.abort();
+    }
+}
+
+/// Takes the repositories of the last complete search from the index, instead of searching all disks.
+///
+/// # Arguments
+///
+/// * `index` - The index of the last complete search.
+/// * `fingerprint` - The fingerprint of the licenses and settings of this run.
+/// * `licenses` - The available licenses, to recognize the license of the changed repositories.
+/// * `print_mode` - The print mode, for errors while writing the index.
+///
+/// # Return
+///
+/// The repositories of the index and the summary of reading it.
+///
+/// # Behavior
+///
+/// Repositories that were not modified since they were indexed are taken as they are, those that changed
+/// (or were analysed with other licenses, see `detection_fingerprint`) are analysed again (like in `init_search`), and those that do not exist
+/// anymore are left out. The index is written again with the current state.
+pub async fn load_index(
+    index: RepositoryIndex,
+    fingerprint: u64,
+    licenses: Vec<GithubLicense>,
+    print_mode: &mut PrintMode,
+) -> (Vec<GitDir>, ScanSummary) {
+    let time = Instant::now();
+    let filter = index.filter.clone();
+    let (mut dirs, changed, removed) = index.check_entries(fingerprint).await;
+
+    let revalidated = changed.len();
+    let (sender, receiver) = channel::<String>(DISCOVERY_BUFFER);
+    tokio::spawn(async move {
+        for path in changed {
+            if sender.send(path).await.is_err() {
+                break;
+            }
+        }
+    });
+    dirs.extend(analyse_repositories(receiver, licenses, &ScanProgress::default()).await);
+    RepositoryIndex::of(filter, fingerprint, &dirs).await.write(print_mode).await;
+
+    let summary = ScanSummary {
+        repositories_found: dirs.len(),
+        duration: time.elapsed(),
+        from_index: true,
+        revalidated,
+        removed,
+        ..ScanSummary::default()
+    };
+    (attribute_repositories(dirs), summary)
+}
+
+/// Analyses the repositories that are sent through the channel.
+///
+/// # Arguments
+///
+/// * `receiver` - The channel the paths of the repositories come through.
+/// * `licenses` - The available licenses, to recognize the license of the repositories.
+/// * `progress` - Counts the found repositories.
+///
+/// # Return
+///
+/// The analysed repositories, once the channel is closed and all of them are analysed.
+///
+/// # Behavior
+///
+/// Every repository is analysed by `GitDir::init` in its own task, with at most
+/// `ANALYSIS_CONCURRENCY` tasks at the same time. A repository that is sent twice
+/// is analysed once, tasks that failed are ignored.
+async fn analyse_repositories(
+    mut receiver: Receiver<String>,
+    licenses: Vec<GithubLicense>,
+    progress: &ScanProgress,
+) -> Vec<GitDir> {
+    let licenses: Arc<[GithubLicense]> = licenses.into();
+    let analysis_slots = Arc::new(Semaphore::new(ANALYSIS_CONCURRENCY));
+    let mut analysis: JoinSet<GitDir> = JoinSet::new();
+    let mut found: HashSet<String> = HashSet::new();
+    while let Some(path) = receiver.recv().await {
+        if !found.insert(path.clone()) {
+            continue;
+        }
+        progress.repositories_found.fetch_add(1, Ordering::Relaxed);
+        let Ok(slot) = analysis_slots.clone().acquire_owned().await else {
+            break;
+        };
+        let licenses = licenses.clone();
+        analysis.spawn(async move {
+            let dir = GitDir::init(path, Some(&licenses)).await;
+            drop(slot);
+            dir
         });
-    println!("Searching took: {}s", time.elapsed().as_secs());
+    }
+
+    let mut dirs: Vec<GitDir> = vec![];
+    while let Some(result) = analysis.join_next().await {
+        if let Ok(dir) = result {
+            dirs.push(dir);
+        }
+    }
     dirs
 }
 
 /**
- * An asynchronous function that starts walking file directories from the root provided.
- * This operation is limited to a maximum depth of one level into the directory structure.
- * The function concurrently searches the directories and avoids those that contain a '$' symbol or
- * start with '.' after the main separator in their name.
+ * An asynchronous function that walks the file directories below the roots provided on a pool of blocking threads.
+ * Each root is checked for being a repository itself, and every directory one level below it becomes a job,
+ * unless the `WalkFilter` excludes it, or skips it as a hidden or system directory.
  *
  * # Parameters
  *
- * - `root`: An initial directory (root), represented as any Display-able (T). It's the starting point for the directory walk.
- * - `licences`: A `Vec<GithubLicense>`. It's a vector of GithubLicense items used in the `walk_deeper` function.
+ * - `roots`: The initial directories (roots), like the mount points of the disks.
+ * - `filter`: The `WalkFilter` that decides which directories are searched.
+ * - `sender`: The channel the found repositories are sent through.
+ * - `progress`: The progress of the search, the walkers stop once it is stopped.
  *
- * The function uses `WalkDir` to create an iterator over the entries within a directory which are explored concurrently using 'tokio::spawn'.
- * The future results of these concurrent operations are then consolidated.
+ * Up to `MAX_WALKER_THREADS` threads (but not more than the machine runs in parallel) share the jobs:
+ * a thread takes the next job once it is done with one, so a single large directory does not hold up the others.
+ * The threads never wait for the runtime, other than when the channel is full.
  *
  * # Returns
  *
- * - `Vec<GitDir>`: This function returns a Vector of GitDir items extracted during directory walking operation.
- *
- * # Error
- *
- * This function does not explicitly handle errors. However, it suppresses any unhandled errors during the execution of futures.
+ * Nothing, the function returns once all jobs are done, the search is stopped or the receiver is gone.
  *
  * # Example
  *
  * ```rust
  * async fn example() {
- *     let root = "root_directory";    // Your root directory goes here
- *     let licenses: Vec<GithubLicense> = Vec::new(); // Your Github licenses array
- *     let result: Vec<GitDir> = start_walking(root, licenses).await;
+ *     let (sender, mut receiver) = tokio::sync::mpsc::channel(256);
+ *     let progress = Arc::new(ScanProgress::default());
+ *     tokio::spawn(start_walking(vec![PathBuf::from("/")], WalkFilter::default(), sender, progress));
+ *     while let Some(path) = receiver.recv().await {
+ *         println!("{}", path);
+ *     }
  * }
  * ```
  *
  **/
-async fn start_walking<T>(
-    root: T,
-    licences: Vec<GithubLicense>,
-) -> Vec<GitDir>
-    where
-        T: Display,
-{
-    let mut task_holder: Vec<JoinHandle<Vec<GitDir>>> = vec![];
-    WalkDir::new(root.to_string())
-        .max_depth(1)
-        .into_iter()
-        .for_each(|dir| {
-            if let Ok(entry) = dir {
-                let tmp = entry.path().display().to_string();
-                if !tmp.contains('$') || !tmp.split(MAIN_SEPARATOR).collect::<Vec<&str>>()[1].starts_with('.') {
-                    task_holder.push(tokio::spawn(walk_deeper(tmp, licences.clone())))
-                }
-            }
+async fn start_walking(roots: Vec<PathBuf>, filter: WalkFilter, sender: Sender<String>, progress: Arc<ScanProgress>) {
+    let listing_filter = filter.clone();
+    let Ok(jobs) = tokio::task::spawn_blocking(move || {
+        let mut jobs: Vec<(PathBuf, bool)> = vec![];
+        roots.into_iter().for_each(|root| {
+            WalkDir::new(&root)
+                .min_depth(1)
+                .max_depth(1)
+                .into_iter()
+                .flatten()
+                .filter(|entry| entry.file_type().is_dir())
+                .filter(|entry| !listing_filter.excludes(entry.path()) && !listing_filter.skips(entry.path()))
+                .for_each(|entry| jobs.push((entry.into_path(), false)));
+            jobs.push((root, true));
         });
-    let mut any_dir: Vec<GitDir> = vec![];
-    futures::future::join_all(task_holder)
-        .await
-        .iter()
-        .filter_map(|future| if let Ok(f) = future { Some(f) } else { None })
-        .for_each(|res| {
-            res.to_vec().iter().for_each(|item| {
-                if !any_dir.contains(item) {
-                    any_dir.push(item.clone())
+        jobs
+    })
+    .await
+    else {
+        return;
+    };
+
+    let walker_threads = std::thread::available_parallelism()
+        .map(|threads| threads.get())
+        .unwrap_or(1)
+        .min(MAX_WALKER_THREADS)
+        .min(jobs.len())
+        .max(1);
+    let jobs = Arc::new(Mutex::new(jobs));
+    let workers = (0..walker_threads)
+        .map(|_| {
+            let (jobs, filter, sender, progress) = (jobs.clone(), filter.clone(), sender.clone(), progress.clone());
+            tokio::task::spawn_blocking(move || {
+                while let Some((root, shallow)) = jobs.lock().ok().and_then(|mut jobs| jobs.pop()) {
+                    if !walk_deeper(root, shallow, &filter, &sender, &progress) {
+                        break;
+                    }
                 }
             })
-        });
-    any_dir
+        })
+        .collect::<Vec<_>>();
+    futures::future::join_all(workers).await;
 }
 
-/// The `walk_deeper` function asynchronously traverses a specified
-/// directory root and checks for Git directories without stepping
+/// The `walk_deeper` function traverses a specified directory root
+/// on a blocking thread and checks for Git directories without stepping
 /// into excluded paths. It performs a recursive search throughout
 /// the nested directories.
 ///
 /// # Arguments
 ///
-/// `root: String` - The root directory from where to start
+/// `root: PathBuf` - The root directory from where to start
 /// the recursive search.
 ///
-/// `licenses: Vec<GithubLicense>` - A vector of `GithubLicense` objects.
+/// `shallow: bool` - Whether only the root itself is checked, without stepping into it.
 ///
-/// # Returns
+/// `filter: &WalkFilter` - Decides which directories are searched and which repositories are taken.
 ///
-/// `Vec<GitDir>` - A vector of `GitDir` objects that are initialized
-/// from the Git directories found during the root traversal.
+/// `sender: &Sender<String>` - The channel the found repositories are sent through.
 ///
-/// # Behavior
+/// `progress: &ScanProgress` - Counts the visited directories, the walker stops once it is stopped.
 ///
-/// - Validates each directory path in the root directory.
-/// - If it is a Git directory, does not exist inside a .cargo directory,
-///   the path without the .git part doesn't contain a . immediately following
-///   a path separator, the path does not contain '$' or 'AppData' string,
-///   it initializes a `GitDir` object from it and added to the resulting vector.
-/// - Uses futures's `block_on` function to initialize the `GitDir` object synchronously.
+/// # Returns
 ///
-/// # Note
+/// `false` if the search is stopped or the receiver is gone, `true` otherwise.
 ///
-/// The function contains commented-out code which accommodates different `OperatingMode`s.
-/// Uncommenting and using it might alter the function behavior.
-async fn walk_deeper(
-    root: String,
-    licenses: Vec<GithubLicense>,
-) -> Vec<GitDir> {
-    WalkDir::new(root)
-        .into_iter()
-        .filter_map(|p_dir| {
-            if let Ok(valid_dir) = p_dir {
-                let path = valid_dir.path().display().to_string();
-                if path.ends_with(format!("{}{}", MAIN_SEPARATOR, ".git").as_str())
-                    && !path.contains(".cargo")
-                    && !path.replace(".git", "").contains(&format!("{}.", MAIN_SEPARATOR))
-                    && !path.contains('$')
-                    && !path.contains("AppData")
-                {
-                    let dir = block_on(GitDir::init(path, Some(licenses.clone())));
-                    Some(dir)
-                } else {
-                    None
-                }
-            } else {
-                None
+/// # Behavior
+///
+/// - Finds working trees by their `.git` entry, which is a directory, or a file for worktrees and submodules,
+///   and bare repositories by their `HEAD`, `objects` and `refs`.
+/// - Does not step into excluded directories, hidden and system directories (unless they are allowed),
+///   pruned directories that are no repository, `.git` directories and bare repositories,
+///   and into repositories unless nested repositories are searched.
+/// - If the repository matches the include patterns, it sends the path of its `.git` entry
+///   (or of the bare repository), waiting while the channel is full.
+fn walk_deeper(
+    root: PathBuf,
+    shallow: bool,
+    filter: &WalkFilter,
+    sender: &Sender<String>,
+    progress: &ScanProgress,
+) -> bool {
+    let walk_dir = if shallow { WalkDir::new(root).max_depth(0) } else { WalkDir::new(root) };
+    let mut walker = walk_dir.into_iter();
+    while let Some(p_dir) = walker.next() {
+        if progress.is_stopped() {
+            return false;
+        }
+        let Ok(valid_dir) = p_dir else {
+            progress.unreadable_directories.fetch_add(1, Ordering::Relaxed);
+            continue;
+        };
+        if !valid_dir.file_type().is_dir() {
+            continue;
+        }
+        let directory = valid_dir.path();
+        progress.visit(directory);
+        // The internals of a repository never contain another one.
+        if valid_dir.file_name() == ".git" || filter.excludes(directory) || filter.skips(directory) {
+            walker.skip_current_dir();
+            continue;
+        }
+
+        let is_bare = is_bare_repository(directory);
+        if !is_bare && !directory.join(".git").exists() {
+            if filter.prunes(directory) {
+                walker.skip_current_dir();
             }
-        })
-        .collect()
+            continue;
+        }
+        if is_bare || !filter.nested_repositories {
+            walker.skip_current_dir();
+        }
+
+        if filter.includes(directory) {
+            let path = if is_bare { directory.to_path_buf() } else { directory.join(".git") };
+            if sender.blocking_send(path.display().to_string()).is_err() {
+                return false;
+            }
+        }
+    }
+    true
 }